@@ -0,0 +1,252 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::error::Error;
+use crate::exec::ExecCommand;
+use crate::squashfs::Squashfs;
+use crate::utils;
+use freedesktop_desktop_entry::DesktopEntry as FdoDesktopEntry;
+use serde::Serialize;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/*
+ * Prefix of everything AppImageLauncher and appimaged create when they integrate an AppImage, followed by
+ * the MD5 of the image's URI: appimagekit_<md5>-<name>.desktop, appimagekit_<md5>_<icon>.png and so on.
+ */
+const INTEGRATION_PREFIX: &str = "appimagekit_";
+
+/* AppImages are ELF executables (the runtime mounting the image appended to them) with "AI" and their type at offset 8. */
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+const APPIMAGE_MAGIC: &[u8] = b"AI\x02";
+
+/* An AppImage a desktop entry launches. */
+#[derive(Clone, Debug, Serialize)]
+pub struct AppImage {
+    pub path: PathBuf,
+    pub version: Option<String>,
+}
+
+impl AppImage {
+    /*
+     * The AppImage behind a desktop entry, if it launches one. That is the case when its command (or
+     * TryExec) is a *.AppImage file, or an executable carrying the AppImage magic whatever it's called.
+     */
+    pub fn from_entry(entry: &FdoDesktopEntry) -> Option<Self> {
+        /* TryExec is a plain path, not a command line. */
+        let try_exec = entry.desktop_entry("TryExec").map(PathBuf::from);
+        let path = entry
+            .exec()
+            .and_then(|e| ExecCommand::parse(e).ok())
+            .map(|c| PathBuf::from(c.program))
+            .into_iter()
+            .chain(try_exec)
+            .find(|p| is_appimage(p) || (p.is_absolute() && runtime_header(p).is_some()))?;
+
+        /*
+         * The version is set in the desktop entry embedded in the image, which the integration tools copy
+         * into the one they install. The name of the file is only a guess.
+         */
+        let version = entry
+            .desktop_entry("X-AppImage-Version")
+            .map(ToOwned::to_owned)
+            .or_else(|| embedded_version(&path))
+            .or_else(|| version_from_name(&path));
+        Some(Self { path, version })
+    }
+
+    /*
+     * Icons, MIME types and other registrations created when the AppImage was integrated into the desktop,
+     * given the Icon key and the path of its desktop entry.
+     */
+    pub fn integration_files(
+        &self,
+        icon: &str,
+        desktop_entry: &Path,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let data_home = utils::data_home()?;
+        let mut files = Vec::new();
+
+        if let Some(id) = integration_id(icon, desktop_entry) {
+            find_prefixed(&data_home.join("icons"), &id, &mut files);
+            find_prefixed(&data_home.join("mime/packages"), &id, &mut files);
+        }
+
+        /* The desktop integration script of appimagekit remembers images it shouldn't ask about again. */
+        if let Some(name) = self.path.file_name() {
+            find_prefixed(
+                &data_home.join("appimagekit"),
+                &name.to_string_lossy(),
+                &mut files,
+            );
+        }
+        Ok(files)
+    }
+}
+
+fn is_appimage(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("appimage"))
+}
+
+/* The ELF header of an AppImage of type 2, the only one still made, if the file is one. */
+fn runtime_header(path: &Path) -> Option<[u8; 64]> {
+    let mut header = [0; 64];
+    fs::File::open(path).ok()?.read_exact(&mut header).ok()?;
+    (header.starts_with(ELF_MAGIC) && header[8..].starts_with(APPIMAGE_MAGIC)).then_some(header)
+}
+
+/* The image is appended to the runtime, whose ELF file ends with its section header table. */
+fn runtime_size(header: &[u8; 64]) -> Option<u64> {
+    let le16 = |at: usize| u16::from_le_bytes([header[at], header[at + 1]]) as u64;
+    /* Only little endian runtimes exist, in either 32 or 64 bit. */
+    let (table, entry_size, entries) = match (header[4], header[5]) {
+        (1, 1) => (
+            u32::from_le_bytes(header[0x20..0x24].try_into().ok()?) as u64,
+            le16(0x2e),
+            le16(0x30),
+        ),
+        (2, 1) => (
+            u64::from_le_bytes(header[0x28..0x30].try_into().ok()?),
+            le16(0x3a),
+            le16(0x3c),
+        ),
+        _ => return None,
+    };
+    table.checked_add(entry_size * entries)
+}
+
+/* The X-AppImage-Version of the desktop entry in the root of the image, which every AppImage has one of. */
+fn embedded_version(path: &Path) -> Option<String> {
+    let image = Squashfs::open(path, runtime_size(&runtime_header(path)?)?)?;
+    let name = image
+        .list("")?
+        .into_iter()
+        .find(|n| n.ends_with(".desktop"))?;
+    let contents = String::from_utf8(image.read(&name)?).ok()?;
+    let entry = FdoDesktopEntry::from_str(&name, &contents, None::<&[String]>).ok()?;
+    entry
+        .desktop_entry("X-AppImage-Version")
+        .map(ToOwned::to_owned)
+}
+
+/* Guesses the version from names like "Obsidian-1.5.3.AppImage" or "app-v2.0-x86_64.AppImage". */
+fn version_from_name(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_string_lossy();
+    stem.split(['-', '_'])
+        .map(|part| part.trim_start_matches(['v', 'V']))
+        .find(|part| part.contains('.') && part.split('.').all(|n| n.parse::<u32>().is_ok()))
+        .map(ToOwned::to_owned)
+}
+
+/* The "appimagekit_<md5>" the integration tool named everything after, going by the icon or the entry. */
+fn integration_id(icon: &str, desktop_entry: &Path) -> Option<String> {
+    let id = |name: &str| {
+        let hash = name.strip_prefix(INTEGRATION_PREFIX)?.get(..32)?;
+        hash.chars()
+            .all(|c| c.is_ascii_hexdigit())
+            .then(|| format!("{}{}", INTEGRATION_PREFIX, hash))
+    };
+    id(icon).or_else(|| id(&desktop_entry.file_stem()?.to_string_lossy()))
+}
+
+/* Every file (not directory) under a directory whose name starts with a prefix. */
+fn find_prefixed(dir: &Path, prefix: &str, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            find_prefixed(&path, prefix, found);
+        } else if entry.file_name().to_string_lossy().starts_with(prefix) {
+            found.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::squashfs;
+
+    fn entry(exec: &Path, extra: &str) -> FdoDesktopEntry {
+        let contents = format!(
+            "[Desktop Entry]\nType=Application\nName=App\nExec={} %U\n{}",
+            exec.display(),
+            extra
+        );
+        FdoDesktopEntry::from_str("app.desktop", &contents, None::<&[String]>).unwrap()
+    }
+
+    /* A 64 bit runtime without sections, followed by an image holding the embedded desktop entry. */
+    fn appimage(embedded: &str) -> Vec<u8> {
+        let mut runtime = [0; 64];
+        runtime[..11].copy_from_slice(b"\x7fELF\x02\x01\x01\0AI\x02");
+        runtime[0x28] = 64;
+        let image = squashfs::test_image(
+            &[("app.desktop", embedded.as_bytes()), ("AppRun", b"")],
+            &[],
+            None,
+        );
+        [&runtime[..], &image].concat()
+    }
+
+    #[test]
+    fn appimages_are_recognized_by_their_magic() {
+        let dir = utils::test_dir("appimage-magic");
+        let app = dir.join("app");
+        fs::write(
+            &app,
+            appimage("[Desktop Entry]\nName=App\nX-AppImage-Version=2.4.1\n"),
+        )
+        .unwrap();
+        let script = dir.join("script");
+        fs::write(&script, "#!/bin/sh\n").unwrap();
+
+        let found = AppImage::from_entry(&entry(&app, "")).unwrap();
+        assert_eq!(found.path, app);
+        assert_eq!(found.version.as_deref(), Some("2.4.1"));
+        /* Integration keys alone don't make a script an AppImage. */
+        assert!(AppImage::from_entry(&entry(&script, "X-AppImage-Version=1.0\n")).is_none());
+    }
+
+    #[test]
+    fn versions_fall_back_to_the_file_name() {
+        let dir = utils::test_dir("appimage-version");
+        let app = dir.join("App-v1.5.3-x86_64.AppImage");
+        fs::write(&app, appimage("[Desktop Entry]\nName=App\n")).unwrap();
+
+        assert_eq!(
+            AppImage::from_entry(&entry(&app, ""))
+                .unwrap()
+                .version
+                .as_deref(),
+            Some("1.5.3")
+        );
+        assert_eq!(
+            AppImage::from_entry(&entry(&app, "X-AppImage-Version=1.6\n"))
+                .unwrap()
+                .version
+                .as_deref(),
+            Some("1.6")
+        );
+    }
+}
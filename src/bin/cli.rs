@@ -0,0 +1,818 @@
+#![warn(clippy::all)]
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use appuninstaller::desktop::{self, DesktopEntry};
+use appuninstaller::error::Error;
+use appuninstaller::exec::{self, ExecCommand};
+use appuninstaller::flatpak::{self, FlatpakRef, RefKind};
+use appuninstaller::history::{History, HistoryEntry};
+use appuninstaller::installroot;
+use appuninstaller::pkgdb::{PackageDb, PackageOwner};
+use appuninstaller::pkgmgr;
+use appuninstaller::plan::{ItemRole, UninstallPlan};
+use appuninstaller::purge::{AppPurgeProcess, AppPurger, RemovalMethod};
+use appuninstaller::safety;
+use appuninstaller::snap::{self, Snap};
+use appuninstaller::usage::{disk_usage, AppUsage};
+use appuninstaller::utils::{format_size, format_timestamp};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = r#"Usage: LinuxAppUninstaller-cli <command> [options]
+
+Commands:
+    list                 List every installed application, except the ones menus hide unless --all is given
+    show <app>           Show details about an application
+    leftovers <app>      List the data directories an application left behind
+    plan <app>           Show everything that removing an application would delete
+    remove <app>         Remove the executable and desktop entry of an application
+    apply <file>         Execute an uninstall plan saved with `plan --save`
+    history              List every application uninstalled so far
+    restore <id>         Put an uninstalled application back from the trash
+    flatpak              List the installed Flatpak apps
+    unused-runtimes      List the Flatpak runtimes that no installed app needs
+
+Options:
+    --json               Print machine readable JSON instead of text
+    --all                (list) Include the entries application menus hide (NoDisplay, OnlyShowIn...)
+    --save <file>        (plan) Save the plan to a file so it can be applied later
+    --purge              (remove) Also delete the leftover data directories (~/.var/app/<id> for Flatpak apps,
+                         ~/snap/<name> for snaps)
+    --yes                (remove, apply) Don't ask for confirmation
+    --permanent          (remove, apply) Delete files for good instead of moving them to the trash
+    --allow-packaged     (plan, remove) Allow apps owned by the system package manager
+    --package-manager    (remove) Remove apps owned by the system package manager through it
+    --override-only      (plan, remove) Only remove the copy of a system desktop entry in your home directory,
+                         which brings back the system entry
+
+<app> is either the name of the application, its desktop file ID or the file name of its desktop entry.
+
+Exit codes:
+    0  Success
+    1  Unknown error
+    2  Invalid usage
+    3  No application matched
+    4  The executable of the application couldn't be found
+    5  A file couldn't be deleted
+    6  The home directory couldn't be found
+    7  A file couldn't be read or written
+    8  The uninstall plan is not valid
+    9  A file has changed since the uninstall plan was made
+    10 A file couldn't be moved to the trash
+    11 A file couldn't be restored
+    12 Restoring would overwrite files that were recreated
+    13 There is nothing to restore for this id
+    14 The application belongs to a package of the system package manager
+    15 No package manager that can remove the package is installed
+    16 The package manager failed
+    17 The application is a Flatpak app or a snap and can only be removed through Flatpak or snap
+    18 A file is protected and was not removed
+    19 The application has no user override to remove"#;
+
+/* Exit code for invalid usage, see USAGE. */
+const USAGE_EXIT_CODE: u8 = 2;
+
+struct Options {
+    json: bool,
+    purge: bool,
+    yes: bool,
+    save: Option<PathBuf>,
+    method: RemovalMethod,
+    allow_packaged: bool,
+    package_manager: bool,
+    override_only: bool,
+    all: bool,
+}
+
+#[derive(serde::Serialize)]
+struct AppDetails<'a> {
+    #[serde(flatten)]
+    entry: &'a DesktopEntry,
+    exec_path: Option<PathBuf>,
+    real_location: Vec<PathBuf>,
+    install_root: Option<PathBuf>,
+    usage: Option<AppUsage>,
+    command: Option<ExecCommand>,
+    package: Option<&'a PackageOwner>,
+    flatpak: Option<FlatpakRef>,
+    snap: Option<Snap>,
+}
+
+/* The part of the preferences of the GTK app that applies here too. */
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct SharedPreferences {
+    protected_paths: Vec<PathBuf>,
+}
+
+impl SharedPreferences {
+    /* Unlike confy::load(), doesn't write the configuration file when there is none yet. */
+    fn load() -> Self {
+        confy::get_configuration_file_path("LinuxAppUninstaller", None)
+            .ok()
+            .filter(|path| path.exists())
+            .and_then(|path| confy::load_path(path).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(serde::Serialize)]
+struct RemoveReport<'a> {
+    name: &'a str,
+    errors: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var("RUST_LOG", "warn");
+    }
+
+    env_logger::init();
+    safety::set_protected(SharedPreferences::load().protected_paths);
+
+    let mut opts = Options {
+        json: false,
+        purge: false,
+        yes: false,
+        save: None,
+        method: RemovalMethod::Trash,
+        allow_packaged: false,
+        package_manager: false,
+        override_only: false,
+        all: false,
+    };
+    let mut args = Vec::new();
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "--json" => opts.json = true,
+            "--save" => match argv.next() {
+                Some(file) => opts.save = Some(PathBuf::from(file)),
+                None => return usage_error("--save needs a file name"),
+            },
+            "--purge" => opts.purge = true,
+            "--yes" | "-y" => opts.yes = true,
+            "--permanent" => opts.method = RemovalMethod::Permanent,
+            "--allow-packaged" => opts.allow_packaged = true,
+            "--package-manager" => opts.package_manager = true,
+            "--override-only" => opts.override_only = true,
+            "--all" => opts.all = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            flag if flag.starts_with('-') => {
+                return usage_error(&format!("Unknown option '{}'", flag))
+            }
+            _ => args.push(arg),
+        }
+    }
+
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["list"] => list(&opts),
+        ["show", app] => show(app, &opts),
+        ["leftovers", app] => leftovers(app, &opts),
+        ["plan", app] => plan(app, &opts),
+        ["remove", app] => remove(app, &opts),
+        ["apply", file] => apply(Path::new(file), &opts),
+        ["history"] => history(&opts),
+        ["flatpak"] => print_refs(&flatpak::installed(RefKind::App), &opts),
+        ["unused-runtimes"] => print_refs(
+            &flatpak::unused_runtimes(
+                &flatpak::installed(RefKind::App),
+                &flatpak::installed(RefKind::Runtime),
+            ),
+            &opts,
+        ),
+        ["restore", id] => match id.parse() {
+            Ok(id) => restore(id, &opts),
+            Err(_) => return usage_error(&format!("Invalid history id '{}'", id)),
+        },
+        [] => return usage_error("No command given"),
+        _ => return usage_error(&format!("Invalid command '{}'", args.join(" "))),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            if opts.json {
+                println!(
+                    "{}",
+                    serde_json::json!({ "error": e.to_string(), "code": e.exit_code() })
+                );
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+fn usage_error(msg: &str) -> ExitCode {
+    eprintln!("{}\n\n{}", msg, USAGE);
+    ExitCode::from(USAGE_EXIT_CODE)
+}
+
+fn print_json(value: &impl serde::Serialize) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).expect("JSON serialization can't fail for our types")
+    );
+}
+
+/* Prints rows as a table, each column padded to its widest cell. */
+fn print_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) {
+    let mut widths = header.map(str::len);
+    for row in rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+
+    let print_row = |cells: [&str; N]| {
+        let line = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, w)| format!("{:<w$}", cell, w = w))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    };
+
+    print_row(header);
+    for row in rows {
+        print_row(row.each_ref().map(String::as_str));
+    }
+}
+
+fn load_sorted() -> Vec<DesktopEntry> {
+    let mut apps = desktop::load_entries();
+    apps.sort_by(|a, b| a.name.cmp(&b.name));
+    apps
+}
+
+fn list(opts: &Options) -> Result<ExitCode, Error> {
+    let mut apps = load_sorted();
+    apps.retain(|a| opts.all || a.hidden.is_none());
+    if opts.json {
+        print_json(&apps);
+    } else {
+        let rows = apps
+            .iter()
+            .map(|a| {
+                let name = match (a.broken, &a.hidden) {
+                    (true, _) => format!("{} (broken)", a.name),
+                    (false, Some(_)) => format!("{} (hidden)", a.name),
+                    (false, None) => a.name.clone(),
+                };
+                [name, a.exec.clone(), a.full_path.clone()]
+            })
+            .collect::<Vec<_>>();
+        print_table(["NAME", "COMMAND", "DESKTOP ENTRY"], &rows);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn show(app: &str, opts: &Options) -> Result<ExitCode, Error> {
+    let apps = load_sorted();
+    let entry = desktop::find_entry(&apps, app)?;
+    let exec_path = entry
+        .appimage
+        .as_ref()
+        .map(|i| i.path.clone())
+        .or_else(|| AppPurger::find_exec(entry));
+    let mut details = AppDetails {
+        entry,
+        real_location: exec_path
+            .as_deref()
+            .map(exec::follow_launcher)
+            .unwrap_or_default(),
+        exec_path,
+        install_root: installroot::find_root(entry),
+        usage: None,
+        command: ExecCommand::parse(&entry.exec).ok(),
+        package: PackageDb::system().entry_owner(entry),
+        flatpak: flatpak::find_app(entry),
+        snap: snap::find_snap(entry),
+    };
+    if details.flatpak.is_none() && details.snap.is_none() {
+        details.usage = Some(AppUsage::of_entry(entry));
+    }
+
+    if opts.json {
+        print_json(&details);
+    } else {
+        let exec_path = details
+            .exec_path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "Not found".to_string());
+        let mut rows = vec![
+            ["Name".to_string(), entry.name.clone()],
+            ["Description".to_string(), entry.description.clone()],
+            ["Command".to_string(), entry.exec.clone()],
+            ["Executable".to_string(), exec_path],
+        ];
+        if !details.real_location.is_empty() {
+            let hops = details
+                .real_location
+                .iter()
+                .map(|p| p.display().to_string());
+            rows.push([
+                "Real location".to_string(),
+                hops.collect::<Vec<_>>().join(" -> "),
+            ]);
+        }
+        if let Some(root) = &details.install_root {
+            rows.push(["Installed in".to_string(), root.display().to_string()]);
+        }
+        if let Some(command) = &details.command {
+            if !command.wrappers.is_empty() {
+                rows.push(["Launched through".to_string(), command.wrappers.join(", ")]);
+            }
+            if let Some(interpreter) = &command.interpreter {
+                let payload = command
+                    .payload
+                    .as_deref()
+                    .unwrap_or("code from the command line");
+                rows.push([
+                    "Interpreter".to_string(),
+                    format!("{}, running {}", interpreter, payload),
+                ]);
+            }
+            if let Some(prefix) = command.wine_prefix() {
+                rows.push(["Wine prefix".to_string(), prefix.display().to_string()]);
+            }
+            if !command.env.is_empty() {
+                let env = command.env.iter().map(|(k, v)| format!("{}={}", k, v));
+                rows.push(["Environment".to_string(), env.collect::<Vec<_>>().join(" ")]);
+            }
+        }
+        rows.extend([
+            ["Desktop entry".to_string(), entry.full_path.clone()],
+            ["Desktop file ID".to_string(), entry.id.clone()],
+            [
+                "Hidden".to_string(),
+                entry
+                    .hidden
+                    .as_ref()
+                    .map_or_else(|| "No".to_string(), |reason| format!("Yes, {}", reason)),
+            ],
+            [
+                "Broken".to_string(),
+                if entry.broken {
+                    "Yes, the program its TryExec key names is missing".to_string()
+                } else {
+                    "No".to_string()
+                },
+            ],
+            [
+                "Shadows".to_string(),
+                if entry.shadowed.is_empty() {
+                    "Nothing".to_string()
+                } else {
+                    entry.shadowed.join(", ")
+                },
+            ],
+            [
+                "Package".to_string(),
+                details.package.map_or_else(
+                    || "None, installed manually".to_string(),
+                    ToString::to_string,
+                ),
+            ],
+        ]);
+        if let Some(image) = &entry.appimage {
+            rows.extend([
+                [
+                    "AppImage".to_string(),
+                    format!(
+                        "{} ({})",
+                        image.path.display(),
+                        format_size(disk_usage(&image.path))
+                    ),
+                ],
+                [
+                    "AppImage version".to_string(),
+                    image
+                        .version
+                        .clone()
+                        .unwrap_or_else(|| "Unknown".to_string()),
+                ],
+            ]);
+        }
+        if let Some(usage) = &details.usage {
+            rows.push(["Size on disk".to_string(), usage.summary()]);
+        }
+        if let Some(app) = &details.flatpak {
+            let data_dir = app.data_dir()?;
+            rows.extend([
+                ["Flatpak ref".to_string(), app.name()],
+                [
+                    "Origin".to_string(),
+                    app.origin.clone().unwrap_or_else(|| "Unknown".to_string()),
+                ],
+                ["Installation".to_string(), app.installation.to_string()],
+                ["Installed size".to_string(), format_size(app.size)],
+                [
+                    "Data directory".to_string(),
+                    format!(
+                        "{} ({})",
+                        data_dir.display(),
+                        format_size(disk_usage(&data_dir))
+                    ),
+                ],
+            ]);
+        }
+        if let Some(snap) = &details.snap {
+            rows.extend(snap_rows(snap)?);
+        }
+        print_table(["FIELD", "VALUE"], &rows);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn leftovers(app: &str, opts: &Options) -> Result<ExitCode, Error> {
+    let apps = load_sorted();
+    let entry = desktop::find_entry(&apps, app)?;
+    let leftovers = AppPurgeProcess::for_entry(entry).find_leftovers()?;
+
+    if opts.json {
+        print_json(&leftovers);
+    } else {
+        let rows = leftovers
+            .iter()
+            .map(|l| {
+                [
+                    l.path.display().to_string(),
+                    l.confidence.to_string(),
+                    l.reason.clone(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        print_table(["PATH", "CONFIDENCE", "REASON"], &rows);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/* Asks the user on the terminal, anything other than "y" or "yes" counts as a no. */
+fn confirm(question: &str) -> bool {
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush().ok();
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer).ok();
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn print_plan(plan: &UninstallPlan) {
+    let rows = plan
+        .items
+        .iter()
+        .map(|i| {
+            [
+                if i.selected { "x" } else { " " }.to_string(),
+                i.path.display().to_string(),
+                format_size(i.size),
+                i.risk.to_string(),
+                i.reason.clone(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    print_table(["", "PATH", "SIZE", "RISK", "REASON"], &rows);
+    println!("\nTotal: {}", format_size(plan.total_size()));
+}
+
+fn plan(app: &str, opts: &Options) -> Result<ExitCode, Error> {
+    let apps = load_sorted();
+    let entry = desktop::find_entry(&apps, app)?;
+    let plan = if opts.override_only {
+        UninstallPlan::user_override(entry)?
+    } else {
+        UninstallPlan::new(entry, opts.allow_packaged)?
+    };
+
+    if let Some(file) = &opts.save {
+        plan.save(file)?;
+    }
+
+    if opts.json {
+        println!("{}", plan.to_json());
+    } else {
+        print_plan(&plan);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/* Shows the plan, asks for confirmation unless --yes was given and executes it. */
+fn execute(plan: &UninstallPlan, opts: &Options) -> ExitCode {
+    if !opts.yes {
+        if !opts.json {
+            print_plan(plan);
+        }
+        let question = match opts.method {
+            RemovalMethod::Trash => {
+                format!("Move the selected items of {} to the trash?", plan.app)
+            }
+            RemovalMethod::Permanent => format!(
+                "Permanently delete the selected items of {}? This cannot be undone.",
+                plan.app
+            ),
+        };
+        if !confirm(&question) {
+            eprintln!("Nothing was removed.");
+            return ExitCode::SUCCESS;
+        }
+    }
+
+    let execution = plan.execute(opts.method);
+    let recorded =
+        History::open_default().and_then(|h| h.append(&HistoryEntry::new(plan, &execution)));
+    let mut errors = execution.errors;
+    errors.extend(recorded.err());
+
+    if opts.json {
+        print_json(&RemoveReport {
+            name: &plan.app,
+            errors: errors.iter().map(ToString::to_string).collect(),
+        });
+    } else {
+        errors.iter().for_each(|e| eprintln!("Error: {}", e));
+        if errors.is_empty() {
+            println!("Removed {}", plan.app);
+        }
+    }
+
+    /* Every error was reported above already, so only the exit code of the first one is left to pass on. */
+    errors
+        .first()
+        .map_or(ExitCode::SUCCESS, |e| ExitCode::from(e.exit_code()))
+}
+
+fn remove(app: &str, opts: &Options) -> Result<ExitCode, Error> {
+    let apps = load_sorted();
+    let entry = desktop::find_entry(&apps, app)?;
+    let mut plan = if opts.override_only {
+        UninstallPlan::user_override(entry)?
+    } else {
+        if let Some(app) = flatpak::find_app(entry) {
+            return remove_flatpak(&app, opts);
+        }
+        if let Some(snap) = snap::find_snap(entry) {
+            return remove_snap(&snap, opts);
+        }
+        if opts.package_manager {
+            if let Some(owner) = PackageDb::system().entry_owner(entry) {
+                return remove_package(owner, opts);
+            }
+        }
+        UninstallPlan::new(entry, opts.allow_packaged)?
+    };
+    if !opts.purge {
+        plan.items
+            .iter_mut()
+            .filter(|i| i.role == ItemRole::Data)
+            .for_each(|i| i.selected = false);
+    }
+
+    Ok(execute(&plan, opts))
+}
+
+/*
+ * Hands the removal over to the package manager, showing its output as it runs. The packages that go
+ * along with it, as found by a dry run, are shown first.
+ */
+fn remove_package(owner: &PackageOwner, opts: &Options) -> Result<ExitCode, Error> {
+    let backend = pkgmgr::backend_for(owner, pkgmgr::backends())?;
+    let packages = pkgmgr::dry_run(backend.as_ref(), &owner.package)?;
+    let argv = pkgmgr::removal_command(backend.as_ref(), &owner.package);
+    let cmdline = pkgmgr::display_command(&argv);
+
+    if !opts.json {
+        println!(
+            "{} is managed by {}, removing it removes these packages:",
+            owner.package,
+            backend.name()
+        );
+        packages.iter().for_each(|p| println!("  {}", p));
+        println!();
+    }
+    if !opts.yes
+        && !confirm(&format!(
+            "Run `{}` to remove {} package(s)?",
+            cmdline,
+            packages.len()
+        ))
+    {
+        eprintln!("Nothing was removed.");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let (output, result) = run_command(&argv, opts);
+    report_command(&argv, output, result, opts)
+}
+
+/* Uninstalls a Flatpak app through flatpak. With --purge its data in ~/.var/app goes as well. */
+fn remove_flatpak(app: &FlatpakRef, opts: &Options) -> Result<ExitCode, Error> {
+    remove_with(&app.uninstall_command(), &app.data_dir()?, opts)
+}
+
+/* Removes a snap through snap after previewing what goes. With --purge its data in ~/snap goes as well. */
+fn remove_snap(snap: &Snap, opts: &Options) -> Result<ExitCode, Error> {
+    if !opts.json {
+        print_table(["FIELD", "VALUE"], &snap_rows(snap)?);
+        println!();
+    }
+    remove_with(&snap.remove_command(), &snap.data_dir()?, opts)
+}
+
+/* What `show` and the removal preview tell about a snap. */
+fn snap_rows(snap: &Snap) -> Result<Vec<[String; 2]>, Error> {
+    let data_dir = snap.data_dir()?;
+    Ok(vec![
+        ["Snap".to_string(), snap.name.clone()],
+        [
+            "Version".to_string(),
+            snap.version
+                .clone()
+                .unwrap_or_else(|| "Unknown".to_string()),
+        ],
+        ["Revision".to_string(), snap.revision.clone()],
+        ["Installed size".to_string(), format_size(snap.size)],
+        [
+            "Data directory".to_string(),
+            format!(
+                "{} ({})",
+                data_dir.display(),
+                format_size(disk_usage(&data_dir))
+            ),
+        ],
+    ])
+}
+
+/* Runs the command that uninstalls an app, and with --purge removes its data directory afterwards. */
+fn remove_with(argv: &[String], data_dir: &Path, opts: &Options) -> Result<ExitCode, Error> {
+    let purge_data = opts.purge && data_dir.exists();
+
+    let mut question = format!("Run `{}`?", pkgmgr::display_command(argv));
+    if purge_data {
+        question = format!(
+            "{} {} ({}) will be removed as well.",
+            question,
+            data_dir.display(),
+            format_size(disk_usage(data_dir))
+        );
+    }
+    if !opts.yes && !confirm(&question) {
+        eprintln!("Nothing was removed.");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let (output, mut result) = run_command(argv, opts);
+    if result.is_ok() && purge_data && data_dir.exists() {
+        result = AppPurger::remove_data(data_dir, opts.method).map(|_| ());
+    }
+    report_command(argv, output, result, opts)
+}
+
+/* Runs a command, printing its output as it comes unless --json was given, in which case it's collected. */
+fn run_command(argv: &[String], opts: &Options) -> (Vec<String>, Result<(), Error>) {
+    let mut output = Vec::new();
+    let result = pkgmgr::run(argv, |line| {
+        if opts.json {
+            output.push(line.to_owned());
+        } else {
+            println!("{}", line);
+        }
+    });
+    (output, result)
+}
+
+fn report_command(
+    argv: &[String],
+    output: Vec<String>,
+    result: Result<(), Error>,
+    opts: &Options,
+) -> Result<ExitCode, Error> {
+    if opts.json {
+        print_json(&serde_json::json!({
+            "command": argv,
+            "output": output,
+            "error": result.as_ref().err().map(ToString::to_string),
+        }));
+        return Ok(result.map_or_else(|e| ExitCode::from(e.exit_code()), |_| ExitCode::SUCCESS));
+    }
+
+    result.map(|_| ExitCode::SUCCESS)
+}
+
+fn print_refs(refs: &[FlatpakRef], opts: &Options) -> Result<ExitCode, Error> {
+    if opts.json {
+        print_json(&refs);
+    } else {
+        let rows = refs
+            .iter()
+            .map(|r| {
+                [
+                    r.id.clone(),
+                    r.branch.clone(),
+                    r.origin.clone().unwrap_or_default(),
+                    r.installation.to_string(),
+                    format_size(r.size),
+                ]
+            })
+            .collect::<Vec<_>>();
+        print_table(["ID", "BRANCH", "ORIGIN", "INSTALLATION", "SIZE"], &rows);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn apply(file: &Path, opts: &Options) -> Result<ExitCode, Error> {
+    Ok(execute(&UninstallPlan::load(file)?, opts))
+}
+
+fn history(opts: &Options) -> Result<ExitCode, Error> {
+    let entries = History::open_default()?.entries()?;
+    if opts.json {
+        print_json(&entries);
+    } else {
+        let rows = entries
+            .iter()
+            .enumerate()
+            .map(|(id, e)| {
+                [
+                    id.to_string(),
+                    format_timestamp(e.timestamp),
+                    e.user.clone(),
+                    e.app.clone(),
+                    e.items.len().to_string(),
+                    format_size(e.bytes_reclaimed),
+                    e.failures.len().to_string(),
+                    if e.restored {
+                        "restored"
+                    } else if e.is_partly_restored() {
+                        "partly restored"
+                    } else if e.is_restorable() {
+                        "in trash"
+                    } else {
+                        "deleted"
+                    }
+                    .to_string(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        print_table(
+            [
+                "ID",
+                "DATE",
+                "USER",
+                "NAME",
+                "ITEMS",
+                "RECLAIMED",
+                "FAILURES",
+                "STATUS",
+            ],
+            &rows,
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn restore(id: usize, opts: &Options) -> Result<ExitCode, Error> {
+    let (entry, errors) = History::open_default()?.restore(id)?;
+
+    if opts.json {
+        print_json(&serde_json::json!({
+            "id": id,
+            "restored": entry.restored,
+            "errors": errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        }));
+    } else {
+        errors.iter().for_each(|e| eprintln!("Error: {}", e));
+        if entry.restored {
+            println!("Restored everything");
+        } else if entry.is_restorable() {
+            println!("The rest was restored, run this again to retry what failed");
+        }
+    }
+
+    Ok(errors
+        .first()
+        .map_or(ExitCode::SUCCESS, |e| ExitCode::from(e.exit_code())))
+}
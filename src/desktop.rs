@@ -1,5 +1,3 @@
-#![allow(deprecated)]
-
 // This file is part of Linux Program Uninstaller.
 ///
 /// Linux Uninstaller - A fast, elegant program uninstaller for Linux
@@ -18,25 +16,49 @@
 ///  
 /// You should have received a copy of the GNU General Public License  
 /// along with this program.  If not, see <https://www.gnu.org/licenses/>.
-use crate::dialog::Dialog;
-use crate::purge::AppPurger;
+use crate::appimage::AppImage;
+use crate::error::Error;
+use crate::flatpak;
+use crate::snap;
 use crate::utils;
 use freedesktop_desktop_entry::DesktopEntry as FdoDesktopEntry;
-use gtk::{prelude::*, Align, Dialog as GtkDialog, ResponseType};
-use gtk::{Button, Image, Label};
-use log::error;
+use log::{error, warn};
 use rayon::prelude::*;
 use std::borrow::Cow;
-use std::fs::{self, OpenOptions};
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 
+#[derive(Clone, Debug, serde::Serialize)]
 pub struct DesktopEntry {
+    /* The desktop file ID, eg. "org.gnome.Nautilus.desktop" or "wine-Programs-foo.desktop". */
+    pub id: String,
+    /* The name in the language of the user, see locales(). */
     pub name: String,
+    /* The untranslated name followed by all of its translations. */
+    #[serde(skip)]
+    pub names: Vec<String>,
     pub exec: String,
     pub description: String,
     pub icon_path: Option<String>,
     pub full_path: String,
+    /* The WM_CLASS of the windows of the app, from the StartupWMClass key. */
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wm_class: Option<String>,
+    /* The directory the app is started in, from the Path key. */
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    /* Entries with the same desktop file ID in directories of lower precedence, which this one hides. */
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub shadowed: Vec<String>,
+    /* Why application menus leave the entry out (NoDisplay, Hidden, OnlyShowIn...), if they do. */
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hidden: Option<String>,
+    /* Set when the program named by TryExec is missing, usually because the app is gone already. */
+    pub broken: bool,
+    /* Set when the entry launches an AppImage. */
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub appimage: Option<AppImage>,
 }
 
 impl DesktopEntry {
@@ -49,224 +71,444 @@ impl DesktopEntry {
         full_path: String,
     ) -> Self {
         Self {
+            id: Path::new(&full_path)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            names: vec![name.clone()],
             name,
             exec,
             description,
             icon_path,
             full_path,
+            wm_class: None,
+            working_dir: None,
+            shadowed: Vec::new(),
+            hidden: None,
+            broken: false,
+            appimage: None,
         }
     }
 
-    pub fn create_button_from_entry(&self) -> Button {
-        let button = Button::new();
-        let container = gtk::Box::new(gtk::Orientation::Horizontal, 4);
-        let icon = Image::from_icon_name(
-            &self
-                .icon_path
-                .clone()
-                .unwrap_or("question-symbolic".to_string()),
-        );
-        let label = Label::new(Some(&self.name));
+    /* Whether this is a copy of a system entry in the home directory, made to change how the app shows up. */
+    pub fn is_user_override(&self) -> bool {
+        !self.shadowed.is_empty()
+            && utils::data_home().is_ok_and(|d| Path::new(&self.full_path).starts_with(d))
+    }
+}
 
-        label.set_halign(gtk::Align::Center);
-        container.append(&icon);
-        container.append(&label);
-        button.set_child(Some(&container));
+/* How deep subdirectories of an applications directory are scanned, which also stops symlink loops. */
+const MAX_DEPTH: usize = 8;
 
-        button
+/*
+ * The directories desktop entries are read from, in order of precedence: the applications directory of
+ * $XDG_DATA_HOME and of every $XDG_DATA_DIRS, followed by the Flatpak exports and the snapd directory in
+ * case they aren't part of $XDG_DATA_DIRS already.
+ */
+pub fn application_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    match utils::data_home() {
+        Ok(dir) => dirs.push(dir.join("applications")),
+        Err(e) => warn!("Skipping the desktop entries of the current user: {}", e),
     }
+    dirs.extend(
+        utils::data_dirs()
+            .into_iter()
+            .map(|d| d.join("applications")),
+    );
+    dirs.extend(flatpak::export_dirs());
+    dirs.push(snap::desktop_dir(Path::new("/")));
+
+    let mut seen = HashSet::new();
+    dirs.retain(|d| seen.insert(fs::canonicalize(d).unwrap_or_else(|_| d.clone())));
+    dirs
+}
+
+/*
+ * The desktop file ID of an entry, that is its path relative to the applications directory with slashes
+ * turned into dashes, eg. "kde4-konsole.desktop" for kde4/konsole.desktop.
+ */
+pub fn desktop_file_id(applications_dir: &Path, path: &Path) -> Option<String> {
+    let parts = path
+        .strip_prefix(applications_dir)
+        .ok()?
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>();
+    Some(parts.join("-"))
+}
+
+/*
+ * Reads every desktop entry in the application directories, subdirectories included. When more than one
+ * has the same desktop file ID, only the one in the directory that comes first counts, as the menu spec
+ * says, and the others are listed as shadowed by it.
+ */
+pub fn load_entries() -> Vec<DesktopEntry> {
+    load_entries_in(&application_dirs())
+}
+
+/* Same as load_entries(), with the given application directories in order of precedence. */
+pub fn load_entries_in(dirs: &[PathBuf]) -> Vec<DesktopEntry> {
+    let found = dirs
+        .par_iter()
+        .map(|dir| {
+            let mut files = Vec::new();
+            find_desktop_files(dir, 0, &mut files);
+            files.sort();
+            files
+        })
+        .collect::<Vec<_>>();
 
-    pub fn appview(&self) -> gtk::Box {
-        let view = gtk::Box::new(gtk::Orientation::Vertical, 16);
-        let title = Label::new(None);
-        let exec_widget = Label::new(Some(&format!("Command: {}", self.exec)));
-        let desc = Label::new(Some(&format!("Description: {}", self.description.clone())));
-        let full = Label::new(Some(&format!("Desktop entry path: {}", self.full_path)));
-        let filesize = Label::new(Some(&format!(
-            "Size on disk: {}KB",
-            utils::get_file_size(
-                which::which(self.exec.clone())
-                    .unwrap_or_default()
-                    .to_string_lossy()
-            )
-            .unwrap_or("0".to_string())
-        )));
-        let openbtn = Button::with_label("Open externally");
-        let dltapp = Button::builder()
-            .label("Delete Application (!)")
-            .css_classes(vec!["destructive-action"])
-            .build();
-        #[cfg(debug_assertions)]
-        let opendata = Button::builder()
-            .label("Open Data Folder(s)")
-            .tooltip_text(r#"Only available on debug builds. Opens the directory that AppUninstaller thinks belongs to this application. Useful to see how accurate is the internal algorithm for detection.
-            Note that if many directories are found only the last one detected will be used."#)
-            .build();
-
-        /* Apparently we can't just use markup directly, we need to set it manually here. */
-        title.set_markup(&format!(
-            "<b><span size='xx-large'>App: {}</span></b>",
-            self.name
-        ));
-        title.set_halign(Align::Start);
-        exec_widget.set_halign(Align::Start);
-        desc.set_halign(Align::Start);
-
-        let name = self.name.clone();
-        let exec = PathBuf::from(self.exec.clone());
-        let entry = PathBuf::from(self.full_path.clone());
-        let entry_c = entry.clone();
-
-        openbtn.connect_clicked(move |_| {
-            open::that_detached(format!("file://{}", entry_c.to_string_lossy())).unwrap_or_else(
-                |e| {
-                    Dialog::new_without_parent(
-                        "Error!",
-                        &format!("Could not open file '{}': {}.", entry_c.display(), e),
-                    );
-                    log::error!("Failed to open {}: {}", entry_c.display(), e)
-                },
-            )
-        });
-        openbtn.set_tooltip_text(Some("Opens the desktop entry using your system's preconfigured application. Useful if you want to modify something in it."));
-        openbtn.set_sensitive(OpenOptions::new().read(true).open(&self.full_path).is_ok());
-
-        dltapp.connect_clicked(move |_| {
-            let choice = GtkDialog::builder()
-                .icon_name("question")
-                .title("Confirm action")
-                .modal(true)
-                .build();
-
-            let content = choice.content_area();
-            content.set_halign(Align::Center);
-            content.set_valign(Align::Center);
-            content.append(&Label::new(Some(
-                "Are you sure you wish to delete this application?",
-            )));
-            choice.add_button("No, close", ResponseType::Close);
-            choice
-                .add_button(&format!("Yes, delete {}", name), ResponseType::Accept)
-                .set_css_classes(&["destructive-action"]);
-            choice.set_default_response(ResponseType::Close);
-
-            let exec = exec.clone();
-            let entry = entry.clone();
-            let name = name.clone();
-            choice.connect_response(move |choice, response| {
-                choice.close();
-                if response == ResponseType::Accept {
-                    AppPurger::purge_app(name.clone(), exec.clone(), entry.clone())
-                }
-            });
-
-            choice.show();
-        });
-
-        #[cfg(debug_assertions)]
-        opendata.connect_clicked(move |b| {
-            let dir_g = AppPurgeProcess::new(name.clone(), true).find_app_files_global();
-            let dir_l = AppPurgeProcess::new(name.clone(), true).find_app_files_home();
-            if !dir_g.is_empty() {
-                let dir = dir_g.last().unwrap();
-                open::that_detached(dir).unwrap_or_else(|e| {
-                    log::error!(
-                        "Couldn't open directory {}: {}",
-                        dir.display(),
-                        e.to_string()
-                    );
-                });
+    /* The effective file of every ID, in order, with the files it shadows. */
+    let mut files: Vec<(String, PathBuf, Vec<String>)> = Vec::new();
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    for (dir, file) in dirs
+        .iter()
+        .zip(found)
+        .flat_map(|(dir, files)| files.into_iter().map(move |f| (dir, f)))
+    {
+        let Some(id) = desktop_file_id(dir, &file) else {
+            continue;
+        };
+        match ids.get(&id) {
+            Some(&i) => files[i].2.push(file.to_string_lossy().into_owned()),
+            None => {
+                ids.insert(id.clone(), files.len());
+                files.push((id, file, Vec::new()));
             }
+        }
+    }
 
-            if !dir_l.is_empty() {
-                let dir = dir_l.last().unwrap();
-                open::that_detached(dir).unwrap_or_else(|e| {
-                    log::error!(
-                        "Couldn't open directory {}: {}",
-                        dir.display(),
-                        e.to_string()
-                    );
-                });
+    let locales = locales();
+    files
+        .into_par_iter()
+        .filter_map(|(id, path, shadowed)| {
+            let mut entry = read_entry(&id, &path, &locales)?;
+            entry.shadowed = shadowed;
+            Some(entry)
+        })
+        .collect()
+}
+
+/* Every *.desktop file below a directory. Symlinks are followed, as on NixOS every entry is one. */
+fn find_desktop_files(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            if dir.exists() {
+                error!("Error reading directory {}: {}", dir.display(), e);
             }
+            return;
+        }
+    };
 
-            if dir_g.is_empty() && dir_l.is_empty() {
-                b.set_label("No directories were found in the system.");
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.is_dir() {
+            if depth < MAX_DEPTH {
+                find_desktop_files(&path, depth + 1, files);
             }
-        });
-
-        view.set_margin_start(16);
-        view.set_margin_end(16);
-        view.set_margin_top(16);
-        view.set_margin_bottom(16);
-        view.set_halign(Align::Start);
-        view.append(&title);
-        view.append(&exec_widget);
-        view.append(&desc);
-        view.append(&full);
-        view.append(&filesize);
-
-        /* The brackets aren't needed here, it's just for readability. */
-        {
-            let c = gtk::Box::new(gtk::Orientation::Horizontal, 4);
-            c.append(&openbtn);
-            #[cfg(debug_assertions)]
-            c.append(&opendata);
-            c.append(&dltapp);
-            view.append(&c);
+        } else if path.is_file() && path.extension().is_some_and(|e| e == "desktop") {
+            files.push(path);
         }
+    }
+}
+
+fn read_entry(id: &str, path: &Path, locales: &[String]) -> Option<DesktopEntry> {
+    let fdo_entry = FdoDesktopEntry::from_path(path, None::<&[String]>).ok()?;
+    let exec = fdo_entry.exec().unwrap_or_default();
+    let name = fdo_entry.name(locales).unwrap_or_default();
+    let icon_path = fdo_entry.icon().unwrap_or_default();
+    let description = fdo_entry.comment(locales).unwrap_or(Cow::Borrowed("None"));
 
-        view
+    let mut entry = DesktopEntry::new(
+        name.to_string(),
+        exec.to_string(),
+        Some(icon_path.to_string()),
+        description.to_string(),
+        path.to_string_lossy().to_string(),
+    );
+    entry.id = id.to_owned();
+    if let Some((untranslated, translations)) = fdo_entry
+        .groups
+        .desktop_entry()
+        .and_then(|g| g.0.get("Name"))
+    {
+        entry.names = vec![untranslated.clone()];
+        for translation in translations.values() {
+            if !entry.names.contains(translation) {
+                entry.names.push(translation.clone());
+            }
+        }
     }
+    entry.wm_class = fdo_entry
+        .desktop_entry("StartupWMClass")
+        .filter(|c| !c.is_empty())
+        .map(ToOwned::to_owned);
+    entry.working_dir = fdo_entry
+        .desktop_entry("Path")
+        .filter(|p| !p.is_empty())
+        .map(ToOwned::to_owned);
+    entry.hidden = hidden_reason(&fdo_entry);
+    entry.broken = fdo_entry
+        .desktop_entry("TryExec")
+        .is_some_and(|program| !program_exists(program));
+    entry.appimage = AppImage::from_entry(&fdo_entry);
+    Some(entry)
 }
 
-pub fn load_entries() -> Vec<DesktopEntry> {
-    let entry_dirs = [
-        format!(
-            "{}/.local/share/applications/",
-            std::env::var("HOME").unwrap()
-        ),
-        "/usr/share/applications".to_string(),
-        "/usr/local/share/applications".to_string(),
-    ];
-
-    let entries = Mutex::new(Vec::new()); // Protects access to entries
-
-    // Process directories in parallel
-    entry_dirs.par_iter().for_each(|dir| {
-        if let Ok(entries_iter) = fs::read_dir(dir) {
-            entries_iter
-                .flatten()
-                .filter(|entry| {
-                    // Filter for only .desktop files
-                    entry.file_type().map(|ft| ft.is_file()).unwrap_or(false)
-                        && entry.file_name().to_string_lossy().ends_with(".desktop")
-                })
-                .for_each(|entry| {
-                    let path = entry.path();
-                    if let Ok(fdo_entry) = FdoDesktopEntry::from_path(&path, None::<&[String]>) {
-                        let exec = fdo_entry.exec().unwrap_or_default();
-                        let name = fdo_entry.name(&["en_US"]).unwrap_or_default();
-                        let icon_path = fdo_entry.icon().unwrap_or_default();
-                        let description = fdo_entry
-                            .comment(&["en_US"])
-                            .unwrap_or(Cow::Borrowed("None"));
-
-                        // Lock and collect entries safely
-                        let mut entries = entries.lock().unwrap();
-                        entries.push(DesktopEntry::new(
-                            name.to_string(),
-                            exec.to_string(),
-                            Some(icon_path.to_string()),
-                            description.to_string(),
-                            path.to_string_lossy().to_string(),
-                        ));
-                    }
-                });
-        } else {
-            error!("Error reading directory: {}", dir);
+/*
+ * The locales to look up translated keys with, most preferred first, picked like gettext does: every
+ * locale of $LANGUAGE, then the first of $LC_ALL, $LC_MESSAGES and $LANG that is set. Each comes with its
+ * fallbacks, eg. "de_DE.UTF-8@euro" gives de_DE@euro, de_DE, de@euro and de.
+ */
+pub fn locales() -> Vec<String> {
+    let var = |name| std::env::var(name).ok().filter(|v: &String| !v.is_empty());
+    let locale = var("LC_ALL")
+        .or_else(|| var("LC_MESSAGES"))
+        .or_else(|| var("LANG"));
+
+    let mut chosen = Vec::new();
+    /* gettext ignores $LANGUAGE when messages aren't translated at all. */
+    if locale.as_deref().is_some_and(|l| !is_c_locale(l)) {
+        if let Some(language) = var("LANGUAGE") {
+            chosen.extend(language.split(':').map(ToOwned::to_owned));
         }
-    });
+    }
+    chosen.extend(locale);
 
-    // Return the collected entries after all parallel work is done
-    entries.into_inner().unwrap()
+    let mut locales = Vec::new();
+    for locale in &chosen {
+        for fallback in locale_fallbacks(locale) {
+            if !locales.contains(&fallback) {
+                locales.push(fallback);
+            }
+        }
+    }
+    locales
+}
+
+fn is_c_locale(locale: &str) -> bool {
+    locale.is_empty() || locale == "C" || locale == "POSIX" || locale.starts_with("C.")
+}
+
+/*
+ * The matching order of the Desktop Entry spec for lang_COUNTRY.ENCODING@MODIFIER, the encoding dropped.
+ * Nothing for the C locale, whose strings are the untranslated ones.
+ */
+fn locale_fallbacks(locale: &str) -> Vec<String> {
+    if is_c_locale(locale) {
+        return Vec::new();
+    }
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale, None),
+    };
+    let locale = locale.split('.').next().unwrap_or(locale);
+    let (lang, country) = match locale.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (locale, None),
+    };
+
+    let mut fallbacks = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        fallbacks.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        fallbacks.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        fallbacks.push(format!("{}@{}", lang, modifier));
+    }
+    fallbacks.push(lang.to_owned());
+    fallbacks
+}
+
+/* Why menus leave an entry out according to the Desktop Entry spec, or None if they show it. */
+fn hidden_reason(entry: &FdoDesktopEntry) -> Option<String> {
+    let kind = entry.type_().unwrap_or("Application");
+    if kind != "Application" {
+        return Some(format!("it is of type {}", kind));
+    }
+    if entry.desktop_entry("Hidden") == Some("true") {
+        return Some("it is marked as deleted (Hidden)".to_string());
+    }
+    if entry.no_display() {
+        return Some("it is marked NoDisplay".to_string());
+    }
+
+    let desktops = std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+    let current = |d: &&str| desktops.split(':').any(|c| c.eq_ignore_ascii_case(d));
+    if let Some(only) = entry.only_show_in() {
+        let only = only
+            .into_iter()
+            .filter(|d| !d.is_empty())
+            .collect::<Vec<_>>();
+        if !only.iter().any(current) {
+            return Some(format!("it is only shown in {}", only.join(", ")));
+        }
+    }
+    if let Some(desktop) = entry
+        .not_show_in()
+        .and_then(|not| not.into_iter().find(current))
+    {
+        return Some(format!("it is not shown in {}", desktop));
+    }
+    None
+}
+
+/* Whether a TryExec program exists, either as an absolute path or somewhere in $PATH. */
+fn program_exists(program: &str) -> bool {
+    let path = Path::new(program);
+    if path.is_absolute() {
+        path.is_file()
+    } else {
+        which::which(path).is_ok()
+    }
+}
+
+/*
+ * Looks up an app by its name (case insensitive, in any language), by its desktop file ID or by the file
+ * name of its desktop entry, the last two with or without the ".desktop" extension.
+ */
+pub fn find_entry<'a>(entries: &'a [DesktopEntry], query: &str) -> Result<&'a DesktopEntry, Error> {
+    let query = query.trim_end_matches(".desktop");
+    entries
+        .iter()
+        .find(|e| e.name.eq_ignore_ascii_case(query))
+        .or_else(|| {
+            entries.iter().find(|e| {
+                e.names
+                    .iter()
+                    .any(|n| n.to_lowercase() == query.to_lowercase())
+            })
+        })
+        .or_else(|| {
+            entries
+                .iter()
+                .find(|e| e.id.trim_end_matches(".desktop") == query)
+        })
+        .or_else(|| {
+            entries.iter().find(|e| {
+                Path::new(&e.full_path)
+                    .file_stem()
+                    .is_some_and(|stem| stem == query)
+            })
+        })
+        .ok_or_else(|| Error::AppNotFound(query.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* Writes a minimal desktop entry, creating the directories leading to it. */
+    fn write_entry(path: &Path, name: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            path,
+            format!(
+                "[Desktop Entry]\nType=Application\nName={}\nExec=true\n",
+                name
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn desktop_file_ids_are_relative_paths() {
+        let dir = Path::new("/usr/share/applications");
+        for (path, id) in [
+            (
+                "/usr/share/applications/org.gnome.Nautilus.desktop",
+                Some("org.gnome.Nautilus.desktop"),
+            ),
+            (
+                "/usr/share/applications/kde4/konsole.desktop",
+                Some("kde4-konsole.desktop"),
+            ),
+            (
+                "/usr/share/applications/wine/Programs/foo.desktop",
+                Some("wine-Programs-foo.desktop"),
+            ),
+            ("/usr/local/share/applications/foo.desktop", None),
+        ] {
+            assert_eq!(
+                desktop_file_id(dir, Path::new(path)).as_deref(),
+                id,
+                "{}",
+                path
+            );
+        }
+
+        let data = utils::test_dir("desktop-subdir-ids");
+        write_entry(&data.join("applications/kde4/konsole.desktop"), "Konsole");
+        write_entry(
+            &data.join("applications/wine/Programs/Foo/foo.desktop"),
+            "Foo",
+        );
+        let mut ids = load_entries_in(&[data.join("applications")])
+            .into_iter()
+            .map(|e| e.id)
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(
+            ids,
+            ["kde4-konsole.desktop", "wine-Programs-Foo-foo.desktop"]
+        );
+    }
+
+    #[test]
+    fn data_home_shadows_data_dirs() {
+        let root = utils::test_dir("desktop-shadowing");
+        let dirs = [
+            root.join("home/.local/share/applications"),
+            root.join("usr/local/share/applications"),
+            root.join("usr/share/applications"),
+        ];
+        write_entry(&dirs[0].join("foo.desktop"), "Foo (edited)");
+        write_entry(&dirs[1].join("foo.desktop"), "Foo (local)");
+        write_entry(&dirs[2].join("foo.desktop"), "Foo");
+        write_entry(&dirs[2].join("bar.desktop"), "Bar");
+        /* Same file name, but another ID */
+        write_entry(&dirs[2].join("vendor/foo.desktop"), "Vendor Foo");
+
+        let mut entries = load_entries_in(&dirs);
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+        let ids = entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, ["bar.desktop", "foo.desktop", "vendor-foo.desktop"]);
+
+        let foo = &entries[1];
+        assert_eq!(foo.name, "Foo (edited)");
+        assert_eq!(foo.full_path, dirs[0].join("foo.desktop").to_string_lossy());
+        assert_eq!(
+            foo.shadowed,
+            [
+                dirs[1].join("foo.desktop").to_string_lossy(),
+                dirs[2].join("foo.desktop").to_string_lossy()
+            ]
+        );
+        assert!(entries[0].shadowed.is_empty());
+        assert!(entries[2].shadowed.is_empty());
+    }
+
+    #[test]
+    fn locales_fall_back_like_the_spec_says() {
+        let cases: [(&str, &[&str]); 9] = [
+            ("sr_RS@latin", &["sr_RS@latin", "sr_RS", "sr@latin", "sr"]),
+            (
+                "de_DE.UTF-8@euro",
+                &["de_DE@euro", "de_DE", "de@euro", "de"],
+            ),
+            ("pt_BR.UTF-8", &["pt_BR", "pt"]),
+            ("ca@valencia", &["ca@valencia", "ca"]),
+            ("fr", &["fr"]),
+            ("C", &[]),
+            ("C.UTF-8", &[]),
+            ("POSIX", &[]),
+            ("", &[]),
+        ];
+        for (locale, fallbacks) in cases {
+            assert_eq!(locale_fallbacks(locale), fallbacks, "{}", locale);
+        }
+    }
 }
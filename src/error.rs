@@ -17,14 +17,85 @@
 ///  
 /// You should have received a copy of the GNU General Public License  
 /// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::pkgdb::{PackageManager, PackageOwner};
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Failed to locate the executable file for this app")]
     ExecNotFound,
-    #[error("Couldn't delete a file for this app ({0})")]
-    CouldNotDelete(String),
+    #[error("Couldn't parse the command line '{0}' of the desktop entry")]
+    InvalidExec(String),
+    #[error("Couldn't delete '{path}' from the filesystem ({1})", path = .0.display())]
+    CouldNotDelete(PathBuf, #[source] std::io::Error),
+    #[error("Couldn't move '{path}' to the trash ({1})", path = .0.display())]
+    CouldNotTrash(PathBuf, #[source] std::io::Error),
+    #[error("Couldn't restore '{path}' ({1})", path = .0.display())]
+    CouldNotRestore(PathBuf, #[source] std::io::Error),
+    #[error("'{path}' was deleted permanently and can't be restored", path = .0.display())]
+    NotInTrash(PathBuf),
+    #[error("Refusing to restore, these paths were recreated in the meantime: {}", .0.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", "))]
+    RestoreConflict(Vec<PathBuf>),
+    #[error("There is no uninstall with id {0} in the history")]
+    HistoryEntryNotFound(usize),
+    #[error("Nothing of {0} can be restored anymore")]
+    NotRestorable(String),
+    #[error("{0} belongs to the package {1}, remove it through your package manager instead")]
+    PackageManaged(String, PackageOwner),
+    #[error("None of the package managers that can remove {0} packages is installed")]
+    NoPackageBackend(PackageManager),
+    #[error("Couldn't run '{0}' ({1})")]
+    CouldNotRun(String, #[source] std::io::Error),
+    #[error("'{0}' failed{code}", code = .1.map(|c| format!(" with exit code {}", c)).unwrap_or_default())]
+    PackageManagerFailed(String, Option<i32>),
+    #[error("{0} is a Flatpak app ({1}), uninstall it through Flatpak instead")]
+    FlatpakApp(String, String),
+    #[error("{0} belongs to the snap {1}, remove it through snap instead")]
+    SnapApp(String, String),
+    #[error("Refusing to remove '{path}', {1}", path = .0.display())]
+    Protected(PathBuf, String),
+    #[error("{0} has no user override of a system desktop entry")]
+    NoUserOverride(String),
+    #[error("Couldn't find the home directory of the current user")]
+    HomeNotFound,
+    #[error("No installed application matches '{0}'")]
+    AppNotFound(String),
+    #[error("Couldn't access '{path}' ({1})", path = .0.display())]
+    Io(PathBuf, #[source] std::io::Error),
+    #[error("Invalid uninstall plan ({0})")]
+    InvalidPlan(String),
+    #[error("'{path}' has changed since the uninstall plan was made", path = .0.display())]
+    PlanMismatch(PathBuf),
     #[error("Unknown error")]
     UnknownError,
 }
+
+impl Error {
+    /*
+     * Exit code reported by the command line interface for this error. Scripts rely on these,
+     * so existing numbers must never change. 1 is used for anything unexpected and 2 for usage errors.
+     */
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::UnknownError => 1,
+            Self::AppNotFound(_) => 3,
+            Self::ExecNotFound | Self::InvalidExec(_) => 4,
+            Self::CouldNotDelete(..) => 5,
+            Self::HomeNotFound => 6,
+            Self::Io(..) => 7,
+            Self::InvalidPlan(_) => 8,
+            Self::PlanMismatch(_) => 9,
+            Self::CouldNotTrash(..) => 10,
+            Self::CouldNotRestore(..) | Self::NotInTrash(_) => 11,
+            Self::RestoreConflict(_) => 12,
+            Self::HistoryEntryNotFound(_) | Self::NotRestorable(_) => 13,
+            Self::PackageManaged(..) => 14,
+            Self::NoPackageBackend(_) => 15,
+            Self::CouldNotRun(..) | Self::PackageManagerFailed(..) => 16,
+            Self::FlatpakApp(..) | Self::SnapApp(..) => 17,
+            Self::Protected(..) => 18,
+            Self::NoUserOverride(_) => 19,
+        }
+    }
+}
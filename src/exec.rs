@@ -0,0 +1,880 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::error::Error;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/* Programs that only set up how the command after them runs. */
+pub(crate) const WRAPPERS: [&str; 22] = [
+    "env",
+    "nice",
+    "ionice",
+    "nohup",
+    "setsid",
+    "taskset",
+    "gamemoderun",
+    "prime-run",
+    "optirun",
+    "primusrun",
+    "pvkrun",
+    "mangohud",
+    "firejail",
+    "torsocks",
+    "proxychains",
+    "proxychains4",
+    "sudo",
+    "pkexec",
+    "gksu",
+    "kdesu",
+    "dbus-launch",
+    "systemd-run",
+];
+
+/* Options of the wrappers above that take a value as the next argument. */
+const WRAPPER_OPTIONS: [(&str, &[&str]); 6] = [
+    ("env", &["-u", "--unset", "-C", "--chdir"]),
+    ("nice", &["-n", "--adjustment"]),
+    ("ionice", &["-c", "--class", "-n", "--classdata"]),
+    ("sudo", &["-u", "--user", "-g", "--group", "-D", "--chdir"]),
+    ("pkexec", &["--user"]),
+    (
+        "systemd-run",
+        &["-p", "--property", "-u", "--unit", "-E", "--setenv"],
+    ),
+];
+
+pub(crate) const SHELLS: [&str; 6] = ["sh", "bash", "dash", "zsh", "ksh", "fish"];
+
+/*
+ * Programs that run a file given to them, which is the app itself, with their options that take a value.
+ * Versioned names like python3.12 or wine64 are matched without the version.
+ */
+const INTERPRETERS: [(&str, &[&str]); 16] = [
+    (
+        "java",
+        &[
+            "-cp",
+            "-classpath",
+            "--class-path",
+            "-p",
+            "--module-path",
+            "--add-modules",
+        ],
+    ),
+    ("python", &["-W", "-X"]),
+    ("pypy", &["-W", "-X"]),
+    ("perl", &[]),
+    ("ruby", &["-I", "-r"]),
+    ("node", &["-r", "--require"]),
+    ("nodejs", &["-r", "--require"]),
+    ("electron", &[]),
+    ("mono", &[]),
+    ("dotnet", &[]),
+    ("wine", &[]),
+    ("sh", &[]),
+    ("bash", &[]),
+    ("dash", &[]),
+    ("zsh", &[]),
+    ("ksh", &[]),
+];
+
+/* Options after which an interpreter runs code given on the command line rather than a file. */
+const INLINE_CODE: [&str; 4] = ["-c", "-e", "-m", "--eval"];
+
+/* How many symlinks and wrapper scripts are followed from a launcher before giving up. */
+const MAX_HOPS: usize = 8;
+
+/* Wrapper scripts are a few lines long, anything bigger is an app of its own. */
+const MAX_WRAPPER_SIZE: u64 = 4096;
+
+/* Field codes the launcher replaces with files, URLs, the icon and so on, see the Desktop Entry spec. */
+const FIELD_CODES: &str = "fFuUdDnNickvm";
+
+/*
+ * The command a desktop entry runs, parsed from its Exec key. Variables set through `env` and wrappers
+ * like `gamemoderun` or `sh -c` are taken apart, so `program` is what actually gets launched.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ExecCommand {
+    /* Variables set before the program, eg. ("GDK_BACKEND", "x11") for `env GDK_BACKEND=x11 app`. */
+    pub env: Vec<(String, String)>,
+    /* Programs the app is launched through, outermost first. */
+    pub wrappers: Vec<String>,
+    pub program: String,
+    /* Arguments of the program, without field codes. */
+    pub args: Vec<String>,
+    /* The name of the interpreter when the program is one, eg. "java" for `java -jar foo.jar`. */
+    pub interpreter: Option<String>,
+    /* The file the interpreter runs (a jar, a script, an exe...), which is the actual app. */
+    pub payload: Option<String>,
+}
+
+impl ExecCommand {
+    /*
+     * Parses an Exec value (with the general escapes of desktop entry values already undone) following the
+     * quoting rules of the Desktop Entry spec.
+     */
+    pub fn parse(exec: &str) -> Result<Self, Error> {
+        split_exec(exec)
+            .and_then(Self::from_args)
+            .ok_or_else(|| Error::InvalidExec(exec.to_owned()))
+    }
+
+    /* Takes apart a command that is already split into arguments. */
+    fn from_args(mut tokens: Vec<String>) -> Option<Self> {
+        let mut command = Self::default();
+
+        loop {
+            let first = tokens.first()?;
+
+            if let Some((name, value)) = assignment(first) {
+                command.env.push((name.to_owned(), value.to_owned()));
+                tokens.remove(0);
+                continue;
+            }
+
+            let name = file_name(first).to_owned();
+            if SHELLS.contains(&name.as_str()) {
+                if let Some(script) = tokens.iter().position(|t| t == "-c").map(|i| i + 1) {
+                    let script = tokens.get(script).cloned().unwrap_or_default();
+                    tokens = shell_command(&script)?;
+                    command.wrappers.push(name);
+                    continue;
+                }
+            }
+
+            if WRAPPERS.contains(&name.as_str()) && tokens.len() > 1 {
+                let takes_value = WRAPPER_OPTIONS
+                    .iter()
+                    .find(|(w, _)| *w == name)
+                    .map_or(&[][..], |(_, options)| *options);
+                let mut i = 1;
+                while i < tokens.len() && tokens[i].starts_with('-') {
+                    /* `env -S "A=1 app --flag"` splits the string into the command */
+                    if name == "env" && ["-S", "--split-string"].contains(&tokens[i].as_str()) {
+                        let split = split_shell(tokens.get(i + 1)?)?;
+                        tokens.splice(i..i + 2, split);
+                        continue;
+                    }
+                    i += if takes_value.contains(&tokens[i].as_str()) {
+                        2
+                    } else {
+                        1
+                    };
+                }
+
+                tokens.drain(..i.min(tokens.len()));
+                command.wrappers.push(name);
+                continue;
+            }
+
+            break;
+        }
+
+        command.program = tokens.remove(0);
+        command.args = tokens;
+        if let Some((interpreter, options)) = interpreter(command.program_name()) {
+            command.interpreter = Some(command.program_name().to_owned());
+            command.payload = payload(interpreter, options, &command.args);
+        }
+        Some(command)
+    }
+
+    /* The file name of the program, eg. "firefox" for /usr/lib/firefox/firefox. */
+    pub fn program_name(&self) -> &str {
+        file_name(&self.program)
+    }
+
+    /*
+     * The file that makes up the app: the payload of an interpreter, or else the program itself if it's a
+     * path, or the first match in $PATH. The interpreter itself is never returned, as it isn't part of the
+     * app, so this is None when it runs code given on the command line. Relative paths are relative to
+     * `working_dir`, the Path key of the entry, or else to the home directory launchers start apps in.
+     */
+    pub fn resolve(&self, working_dir: Option<&Path>) -> Option<PathBuf> {
+        let absolute = |path: PathBuf| -> Option<PathBuf> {
+            let path = match working_dir {
+                _ if path.is_absolute() => path,
+                Some(dir) => dir.join(path),
+                None => Path::new(&std::env::var_os("HOME")?).join(path),
+            };
+            path.exists().then_some(clean_path(&path))
+        };
+        if self.interpreter.is_some() {
+            let payload = self.payload.as_ref()?;
+            /* Wine also takes Windows paths, C: being drive_c in the prefix. */
+            let path = match payload.strip_prefix("C:").or(payload.strip_prefix("c:")) {
+                Some(rest) => rest
+                    .split(['\\', '/'])
+                    .filter(|c| !c.is_empty())
+                    .fold(self.wine_prefix()?.join("drive_c"), |path, c| path.join(c)),
+                None => PathBuf::from(payload),
+            };
+            return absolute(path);
+        }
+        if self.program.contains('/') {
+            return absolute(PathBuf::from(&self.program));
+        }
+        which::which(&self.program).ok()
+    }
+
+    /*
+     * The Wine prefix a Windows app is installed in, going by where its exe is, then by $WINEPREFIX and
+     * finally by the default prefix.
+     */
+    pub fn wine_prefix(&self) -> Option<PathBuf> {
+        if self.interpreter.as_deref().and_then(interpreter)?.0 != "wine" {
+            return None;
+        }
+        if let Some((prefix, _)) = self
+            .payload
+            .as_deref()
+            .and_then(|p| p.split_once("/drive_c/"))
+        {
+            return Some(PathBuf::from(prefix));
+        }
+        match self.env.iter().find(|(name, _)| name == "WINEPREFIX") {
+            Some((_, prefix)) => Some(PathBuf::from(prefix)),
+            None => std::env::var_os("WINEPREFIX")
+                .map(PathBuf::from)
+                .or_else(|| Some(Path::new(&std::env::var_os("HOME")?).join(".wine"))),
+        }
+    }
+}
+
+/*
+ * Where a launcher really leads: every symlink it goes through and every simple wrapper script that execs
+ * into something else, in order, so the last one is the real program. Empty when the launcher is the
+ * program itself.
+ */
+pub fn follow_launcher(launcher: &Path) -> Vec<PathBuf> {
+    let mut hops: Vec<PathBuf> = Vec::new();
+    let mut current = launcher.to_path_buf();
+    while hops.len() < MAX_HOPS {
+        let next = match fs::read_link(&current) {
+            Ok(target) => current.parent().unwrap_or(Path::new("/")).join(target),
+            Err(_) => match wrapper_target(&current) {
+                Some(target) => target,
+                None => break,
+            },
+        };
+        let next = clean_path(&next);
+        if !next.exists() || next == launcher || hops.contains(&next) {
+            break;
+        }
+        hops.push(next.clone());
+        current = next;
+    }
+    hops
+}
+
+/*
+ * The program a shell wrapper script starts. Only small scripts are looked at: a shebang naming a known
+ * shell, variable assignments, `cd`, and either an `exec` line or a single command passing the arguments
+ * on, eg.
+ *
+ *     #!/bin/sh
+ *     APPDIR=/opt/vendor/app
+ *     cd "$APPDIR"
+ *     exec ./bin/app "$@"
+ */
+fn wrapper_target(path: &Path) -> Option<PathBuf> {
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_WRAPPER_SIZE {
+        return None;
+    }
+    let script = fs::read_to_string(path).ok()?;
+    let shebang = script.lines().next()?.strip_prefix("#!")?;
+    let shell = shebang
+        .split_whitespace()
+        .map(file_name)
+        .find(|w| *w != "env")?;
+    if !SHELLS.contains(&shell) {
+        return None;
+    }
+
+    let mut vars = HashMap::new();
+    if let Some(home) = std::env::var_os("HOME") {
+        vars.insert("HOME".to_string(), home.to_string_lossy().into_owned());
+    }
+    let mut commands = Vec::new();
+    let mut dir: Option<PathBuf> = None;
+    for line in script.lines().skip(1).map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let forwards = ["$@", "${@}", "$*", "${*}"]
+            .iter()
+            .any(|a| line.contains(a));
+        let words = split_shell(&substitute(line, &vars, path)?)?;
+        if let [word] = words.as_slice() {
+            if let Some((name, value)) = assignment(word) {
+                vars.insert(name.to_owned(), value.to_owned());
+                continue;
+            }
+        }
+        if let [cd, to] = words.as_slice() {
+            if cd == "cd" {
+                dir = Some(dir.unwrap_or_default().join(to));
+                continue;
+            }
+        }
+        commands.push((words, forwards));
+    }
+
+    /* Without exec, the command must at least pass the arguments on, or it is a script of its own */
+    let command = match commands
+        .iter()
+        .find(|(c, _)| c.first().is_some_and(|w| w == "exec"))
+    {
+        Some((command, _)) => command[1..].to_vec(),
+        None if commands.len() == 1 && commands[0].1 => commands.pop()?.0,
+        None => return None,
+    };
+    ExecCommand::from_args(command)?.resolve(dir.as_deref())
+}
+
+/*
+ * Expands the variables of a line of a wrapper script, along with $0 and the $(dirname ...) and
+ * $(readlink -f ...) commands used to find where the script is. Arguments ($@, $1...) expand to nothing.
+ * None when anything else is used, as the script is then more than a simple wrapper.
+ */
+fn substitute(line: &str, vars: &HashMap<String, String>, script: &Path) -> Option<String> {
+    let mut expanded = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            expanded.push(c);
+            continue;
+        }
+
+        let name = match chars.peek() {
+            Some('(') => {
+                chars.next();
+                let mut depth = 1;
+                let mut inner = String::new();
+                for c in chars.by_ref() {
+                    depth += match c {
+                        '(' => 1,
+                        ')' => -1,
+                        _ => 0,
+                    };
+                    if depth == 0 {
+                        break;
+                    }
+                    inner.push(c);
+                }
+                let words = split_shell(&substitute(&inner, vars, script)?)?;
+                let value = match words.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+                    ["dirname", path] => Path::new(path).parent()?.to_path_buf(),
+                    ["readlink", "-f", path] | ["realpath", path] => fs::canonicalize(path).ok()?,
+                    _ => return None,
+                };
+                expanded.push_str(&value.to_string_lossy());
+                continue;
+            }
+            Some('{') => {
+                chars.next();
+                let inner = chars.by_ref().take_while(|c| *c != '}').collect::<String>();
+                match inner.split_once(":-").or_else(|| inner.split_once('-')) {
+                    Some((name, default)) => {
+                        expanded.push_str(vars.get(name).map_or(default, String::as_str));
+                        continue;
+                    }
+                    None => inner,
+                }
+            }
+            Some(c) if *c == '@' || *c == '*' || c.is_ascii_digit() => chars.next()?.to_string(),
+            _ => {
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                name
+            }
+        };
+
+        match name.as_str() {
+            "0" => expanded.push_str(&script.to_string_lossy()),
+            "@" | "*" => (),
+            n if n.chars().all(|c| c.is_ascii_digit()) && !n.is_empty() => (),
+            "" => expanded.push('$'),
+            n => expanded.push_str(vars.get(n)?),
+        }
+    }
+    Some(expanded)
+}
+
+/* A path with "." and ".." taken out, without resolving symlinks. */
+fn clean_path(path: &Path) -> PathBuf {
+    let mut cleaned = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                cleaned.pop();
+            }
+            Component::CurDir => (),
+            c => cleaned.push(c),
+        }
+    }
+    cleaned
+}
+
+/* Whether a program, given by its file name, is an interpreter we know of, eg. "python3.12". */
+pub fn is_interpreter(name: &str) -> bool {
+    interpreter(name).is_some()
+}
+
+/* The known interpreter a program is, with the options of it that take a value. */
+fn interpreter(name: &str) -> Option<(&'static str, &'static [&'static str])> {
+    let unversioned = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    INTERPRETERS
+        .iter()
+        .find(|(i, _)| *i == name || *i == unversioned)
+        .copied()
+}
+
+/*
+ * The file an interpreter is asked to run: the argument of `java -jar`, otherwise the first argument that
+ * isn't an option (or, for java, the first entry of the class path). Subcommands like `dotnet exec` and
+ * `wine start /unix` are skipped.
+ */
+fn payload(interpreter: &str, takes_value: &[&str], args: &[String]) -> Option<String> {
+    let mut class_path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-jar" if interpreter == "java" => return args.next().cloned(),
+            "exec" if interpreter == "dotnet" => (),
+            "start" | "/unix" if interpreter == "wine" => (),
+            a if a.starts_with('/')
+                && a.len() > 1
+                && a[1..].chars().all(|c| c.is_ascii_alphabetic()) =>
+            {
+                /* Options of wine start, eg. /wait */
+                if interpreter != "wine" {
+                    return Some(arg.clone());
+                }
+            }
+            a if INLINE_CODE.contains(&a) => return None,
+            a if takes_value.contains(&a) => {
+                let value = args.next()?;
+                if ["-cp", "-classpath", "--class-path"].contains(&a) {
+                    class_path = value
+                        .split(':')
+                        .next()
+                        .map(|p| p.trim_end_matches("/*").to_owned());
+                }
+            }
+            a if a.starts_with('-') => (),
+            _ if interpreter == "java" => return class_path,
+            _ => return Some(arg.clone()),
+        }
+    }
+    None
+}
+
+fn file_name(program: &str) -> &str {
+    program.rsplit('/').next().unwrap_or(program)
+}
+
+/* NAME=VALUE, as understood by `env` and shells. */
+fn assignment(token: &str) -> Option<(&str, &str)> {
+    let (name, value) = token.split_once('=')?;
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then_some((name, value))
+}
+
+/*
+ * Splits an Exec value into arguments. Arguments are separated by spaces and can be quoted with double
+ * quotes, inside which `"`, `` ` ``, `$` and `\` are escaped with a backslash. Arguments that are only a
+ * field code are dropped, "%%" becomes "%" and any other `%` is kept, as in `gamemoderun %command%`. None
+ * if a quote isn't closed.
+ */
+fn split_exec(exec: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    /* Whether there's an argument at all, even an empty one like "" */
+    let mut started = false;
+    let mut quoted = false;
+    /* Whether the argument so far is a field code, which is dropped unless something follows it */
+    let mut field_code = false;
+    let mut chars = exec.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                started = true;
+            }
+            '\\' if quoted => arg.push(chars.next()?),
+            /* Not part of the spec, but common enough in `sh -c '...'` to be worth understanding. */
+            '\'' if !quoted => {
+                started = true;
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => arg.push(c),
+                    }
+                }
+            }
+            '%' if !quoted => {
+                let next = chars.next_if(|c| !c.is_whitespace());
+                field_code = !started && next.is_some_and(|c| FIELD_CODES.contains(c));
+                match next {
+                    Some('%') => arg.push('%'),
+                    Some(code) => {
+                        arg.push('%');
+                        arg.push(code);
+                    }
+                    None => arg.push('%'),
+                }
+                started = true;
+                continue;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if (started || !arg.is_empty()) && !field_code {
+                    args.push(std::mem::take(&mut arg));
+                }
+                arg.clear();
+                started = false;
+            }
+            c => {
+                arg.push(c);
+                started = true;
+            }
+        }
+        field_code = false;
+    }
+
+    if quoted {
+        return None;
+    }
+    if (started || !arg.is_empty()) && !field_code {
+        args.push(arg);
+    }
+    Some(args)
+}
+
+/*
+ * The command a `sh -c` script ends up running. Only simple scripts are understood: a list of commands
+ * separated by `;`, `&&` or `||`, where the last one is the app and an earlier `cd` tells where it is.
+ */
+fn shell_command(script: &str) -> Option<Vec<String>> {
+    let mut commands = vec![Vec::new()];
+    for token in split_shell(script)? {
+        match token.as_str() {
+            ";" | "&&" | "||" | "|" | "&" => commands.push(Vec::new()),
+            _ => match token.strip_suffix(';') {
+                Some(token) => {
+                    commands.last_mut()?.push(token.to_owned());
+                    commands.push(Vec::new());
+                }
+                None => commands.last_mut()?.push(token),
+            },
+        }
+    }
+    commands.retain(|c| !c.is_empty());
+
+    let dir = commands
+        .iter()
+        .filter(|c| c[0] == "cd")
+        .filter_map(|c| c.get(1))
+        .next_back()
+        .cloned();
+    let mut command = commands.into_iter().rfind(|c| c[0] != "cd")?;
+    if command[0] == "exec" && command.len() > 1 {
+        command.remove(0);
+    }
+
+    let home = std::env::var("HOME").ok();
+    for arg in command.iter_mut() {
+        if let (Some(home), Some(rest)) =
+            (&home, arg.strip_prefix("~/").or(arg.strip_prefix("$HOME/")))
+        {
+            *arg = Path::new(home).join(rest).to_string_lossy().into_owned();
+        }
+    }
+    if let Some(dir) = dir {
+        if command[0].contains('/') && !command[0].starts_with('/') {
+            command[0] = Path::new(&dir)
+                .join(command[0].trim_start_matches("./"))
+                .to_string_lossy()
+                .into_owned();
+        }
+    }
+    Some(command)
+}
+
+/* Splits a shell script into words, with single quotes, double quotes and backslashes as a shell does. */
+fn split_shell(script: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut started = false;
+    let mut chars = script.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                started = true;
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '"' => {
+                started = true;
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => word.push(chars.next()?),
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                word.push(chars.next()?);
+                started = true;
+            }
+            c if c.is_whitespace() => {
+                if started {
+                    words.push(std::mem::take(&mut word));
+                }
+                started = false;
+            }
+            c => {
+                word.push(c);
+                started = true;
+            }
+        }
+    }
+
+    if started {
+        words.push(word);
+    }
+    Some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    fn parse(exec: &str) -> ExecCommand {
+        ExecCommand::parse(exec).unwrap_or_else(|e| panic!("{}: {}", exec, e))
+    }
+
+    #[test]
+    fn exec_values_are_split() {
+        let cases: [(&str, &str, &[&str]); 10] = [
+            (
+                r#""/opt/My App/app" --flag"#,
+                "/opt/My App/app",
+                &["--flag"],
+            ),
+            (
+                r#"/usr/bin/app "two words" ''"#,
+                "/usr/bin/app",
+                &["two words", ""],
+            ),
+            (
+                r#"app "say \"hi\"" "a\\b" "\$HOME" "\`x\`""#,
+                "app",
+                &[r#"say "hi""#, r"a\b", "$HOME", "`x`"],
+            ),
+            ("app %f", "app", &[]),
+            ("app %U --new-window", "app", &["--new-window"]),
+            ("app --icon %i %c %k", "app", &["--icon"]),
+            ("app %%U 100% %f%%", "app", &["%U", "100%", "%f%"]),
+            ("app --file=%f", "app", &["--file=%f"]),
+            (r#"app "%f""#, "app", &["%f"]),
+            ("gamemoderun %command%", "%command%", &[]),
+        ];
+        for (exec, program, args) in cases {
+            let command = parse(exec);
+            assert_eq!(command.program, program, "{}", exec);
+            assert_eq!(command.args, args, "{}", exec);
+        }
+        assert!(ExecCommand::parse(r#"app "unclosed"#).is_err());
+    }
+
+    #[test]
+    fn env_assignments_are_taken_apart() {
+        let command = parse("env GDK_BACKEND=x11 FOO=a=b app %u");
+        assert_eq!(command.wrappers, ["env"]);
+        assert_eq!(
+            command.env,
+            [
+                ("GDK_BACKEND".to_owned(), "x11".to_owned()),
+                ("FOO".to_owned(), "a=b".to_owned())
+            ]
+        );
+        assert_eq!(command.program, "app");
+
+        let command = parse(r#"env -u DISPLAY -S "WAYLAND=1 /opt/app/run --x" %F"#);
+        assert_eq!(command.env, [("WAYLAND".to_owned(), "1".to_owned())]);
+        assert_eq!(command.program, "/opt/app/run");
+        assert_eq!(command.args, ["--x"]);
+    }
+
+    #[test]
+    fn shell_commands_are_taken_apart() {
+        let cases = [
+            (r#"sh -c "cd /opt/foo && ./run --x""#, "/opt/foo/run", "sh"),
+            (
+                "bash -c 'cd \"/opt/My Foo\"; exec ./bin/foo'",
+                "/opt/My Foo/bin/foo",
+                "bash",
+            ),
+            (
+                r#"bash -c "exec /opt/foo/app \"\$@\"" foo %F"#,
+                "/opt/foo/app",
+                "bash",
+            ),
+            ("/bin/sh -c 'cd /opt/foo || exit; app'", "app", "sh"),
+        ];
+        for (exec, program, shell) in cases {
+            let command = parse(exec);
+            assert_eq!(command.program, program, "{}", exec);
+            assert_eq!(command.wrappers, [shell], "{}", exec);
+        }
+    }
+
+    #[test]
+    fn nested_wrappers_are_skipped() {
+        let command =
+            parse("nice -n 10 ionice -c 3 firejail --noprofile --private=/tmp app --x %U");
+        assert_eq!(command.wrappers, ["nice", "ionice", "firejail"]);
+        assert_eq!(command.program, "app");
+        assert_eq!(command.args, ["--x"]);
+
+        let command = parse("pkexec env DISPLAY=:0 /usr/bin/app");
+        assert_eq!(command.wrappers, ["pkexec", "env"]);
+        assert_eq!(command.env, [("DISPLAY".to_owned(), ":0".to_owned())]);
+        assert_eq!(command.program, "/usr/bin/app");
+
+        let command = parse("env MANGOHUD=1 mangohud gamemoderun sh -c 'exec ~/Games/game'");
+        assert_eq!(command.wrappers, ["env", "mangohud", "gamemoderun", "sh"]);
+        assert_eq!(command.program.rsplit('/').next(), Some("game"));
+    }
+
+    #[test]
+    fn interpreter_payloads_are_resolved() {
+        let dir = utils::test_dir("exec-interpreters");
+        let drive_c = dir.join("prefix/drive_c/Program Files/App");
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        fs::create_dir_all(&drive_c).unwrap();
+        for file in ["app.jar", "main.py", "App.exe"] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        fs::write(drive_c.join("app.exe"), "").unwrap();
+
+        let cases = [
+            ("java -Xmx1g -jar app.jar %f", "java", Some("app.jar")),
+            (
+                "java -cp lib/*:other.jar org.example.Main",
+                "java",
+                Some("lib"),
+            ),
+            (
+                "python3.12 -u main.py --debug",
+                "python3.12",
+                Some("main.py"),
+            ),
+            ("mono App.exe", "mono", Some("App.exe")),
+            ("python3 -m http.server", "python3", None),
+        ];
+        for (exec, interpreter, payload) in cases {
+            let command = parse(exec);
+            assert_eq!(
+                command.interpreter.as_deref(),
+                Some(interpreter),
+                "{}",
+                exec
+            );
+            assert_eq!(command.payload.as_deref(), payload, "{}", exec);
+            assert_eq!(
+                command.resolve(Some(&dir)),
+                payload.map(|p| dir.join(p)),
+                "{}",
+                exec
+            );
+        }
+
+        /* A relative payload is found in the working directory of the entry, not in ours */
+        assert_eq!(
+            parse("python3 main.py").resolve(Some(Path::new("/nonexistent"))),
+            None
+        );
+
+        let command = parse(&format!(
+            r#"env WINEPREFIX={} wine "C:\\Program Files\\App\\app.exe""#,
+            dir.join("prefix").display()
+        ));
+        assert_eq!(command.interpreter.as_deref(), Some("wine"));
+        assert_eq!(command.resolve(None), Some(drive_c.join("app.exe")));
+    }
+
+    #[test]
+    fn wrapper_scripts_are_followed() {
+        let dir = utils::test_dir("exec-wrappers");
+        let app = dir.join("opt/app/bin/app");
+        fs::create_dir_all(app.parent().unwrap()).unwrap();
+        fs::write(&app, [0x7f, b'E', b'L', b'F', 0, 0]).unwrap();
+
+        let execs = dir.join("execs");
+        fs::write(
+            &execs,
+            format!("#!/bin/sh\n# Launcher\nexec \"{}\" \"$@\"\n", app.display()),
+        )
+        .unwrap();
+        assert_eq!(wrapper_target(&execs), Some(app.clone()));
+
+        let cds = dir.join("cds");
+        fs::write(
+            &cds,
+            format!(
+                "#!/usr/bin/env bash\nAPPDIR={}\ncd \"$APPDIR\"\nexec ./bin/app \"$@\"\n",
+                dir.join("opt/app").display()
+            ),
+        )
+        .unwrap();
+        assert_eq!(wrapper_target(&cds), Some(app.clone()));
+
+        /* Symlinks and scripts are followed in order, the binary at the end is not */
+        std::os::unix::fs::symlink(&cds, dir.join("link")).unwrap();
+        assert_eq!(follow_launcher(&dir.join("link")), [cds, app.clone()]);
+        assert_eq!(wrapper_target(&app), None);
+        assert!(follow_launcher(&app).is_empty());
+
+        /* A script doing more than starting something else is an app of its own */
+        let own = dir.join("own");
+        fs::write(&own, "#!/bin/sh\necho hello\nsleep 1\n").unwrap();
+        assert_eq!(wrapper_target(&own), None);
+    }
+}
@@ -0,0 +1,471 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::desktop::DesktopEntry;
+use crate::error::Error;
+use crate::exec::ExecCommand;
+use crate::usage;
+use crate::utils;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/* Where a ref is installed, flatpak keeps one installation per user and one for the whole system. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Installation {
+    User,
+    System,
+}
+
+impl Installation {
+    /* The root of the installation, eg. /var/lib/flatpak for the system one. */
+    pub fn path(self) -> Result<PathBuf, Error> {
+        match self {
+            Self::User => Ok(utils::data_home()?.join("flatpak")),
+            Self::System => Ok(PathBuf::from("/var/lib/flatpak")),
+        }
+    }
+}
+
+impl fmt::Display for Installation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::User => "user",
+            Self::System => "system",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RefKind {
+    App,
+    Runtime,
+}
+
+impl fmt::Display for RefKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::App => "app",
+            Self::Runtime => "runtime",
+        })
+    }
+}
+
+/* An app or runtime deployed in a flatpak installation. */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FlatpakRef {
+    pub kind: RefKind,
+    pub id: String,
+    pub arch: String,
+    pub branch: String,
+    /* The remote it was installed from, eg. "flathub". */
+    pub origin: Option<String>,
+    pub installation: Installation,
+    /* The deployed files, that is <installation>/<kind>/<id>/<arch>/<branch>/active */
+    pub path: PathBuf,
+    /* In bytes. Deployed files are hardlinks to the repository, so this is what uninstalling frees at most. */
+    pub size: u64,
+    /* For apps, the runtime they run on as "<id>/<arch>/<branch>". */
+    pub runtime: Option<String>,
+    /* The extension points declared in the metadata, eg. "org.freedesktop.Platform.GL". */
+    pub extension_points: Vec<ExtensionPoint>,
+}
+
+/* An [Extension <name>] group of the metadata of a ref, which other runtimes can be plugged into. */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ExtensionPoint {
+    pub name: String,
+    /* The branches of extensions that fit, from the version or versions key, or else the branch of the ref. */
+    pub versions: Vec<String>,
+}
+
+impl ExtensionPoint {
+    /* Whether a runtime plugs into this extension point, which belongs to a ref of the given arch. */
+    fn accepts(&self, runtime: &FlatpakRef, arch: &str) -> bool {
+        let named = runtime.id == self.name || runtime.id.starts_with(&format!("{}.", self.name));
+        named && runtime.arch == arch && self.versions.contains(&runtime.branch)
+    }
+}
+
+impl FlatpakRef {
+    /* The full ref, eg. "app/org.mozilla.firefox/x86_64/stable". */
+    pub fn name(&self) -> String {
+        format!("{}/{}/{}/{}", self.kind, self.id, self.arch, self.branch)
+    }
+
+    /* Where a sandboxed app keeps its data, config and cache. */
+    pub fn data_dir(&self) -> Result<PathBuf, Error> {
+        Ok(utils::home_dir()?.join(".var/app").join(&self.id))
+    }
+
+    /* The command that uninstalls the ref. Flatpak asks for the password itself for system installations. */
+    pub fn uninstall_command(&self) -> Vec<String> {
+        vec![
+            "flatpak".into(),
+            "uninstall".into(),
+            "--noninteractive".into(),
+            format!("--{}", self.installation),
+            self.name(),
+        ]
+    }
+}
+
+/* Directories flatpak exports the desktop entries of installed apps to, user installation first. */
+pub fn export_dirs() -> Vec<PathBuf> {
+    [Installation::User, Installation::System]
+        .into_iter()
+        .filter_map(|i| i.path().ok())
+        .map(|p| p.join("exports/share/applications"))
+        .collect()
+}
+
+/* Every deployed ref of a kind, in both installations. */
+pub fn installed(kind: RefKind) -> Vec<FlatpakRef> {
+    [Installation::User, Installation::System]
+        .into_iter()
+        .filter_map(|i| Some(deployments(&i.path().ok()?, i, kind, None)))
+        .flatten()
+        .collect()
+}
+
+/*
+ * The refs of a kind deployed under the root of an installation, optionally only those of one id.
+ * Their layout is <root>/<kind>/<id>/<arch>/<branch>/active, with "active" linking to the deployed commit.
+ */
+pub fn deployments(
+    root: &Path,
+    installation: Installation,
+    kind: RefKind,
+    id: Option<&str>,
+) -> Vec<FlatpakRef> {
+    let base = root.join(kind.to_string());
+    let ids = match id {
+        Some(id) => vec![id.to_owned()],
+        None => subdirs(&base),
+    };
+
+    let mut found = Vec::new();
+    for id in ids {
+        for arch in subdirs(&base.join(&id))
+            .into_iter()
+            .filter(|a| a != "current")
+        {
+            for branch in subdirs(&base.join(&id).join(&arch)) {
+                found.push((id.clone(), arch.clone(), branch));
+            }
+        }
+    }
+
+    let mut refs = found
+        .into_par_iter()
+        .filter_map(|(id, arch, branch)| {
+            let path = base.join(&id).join(&arch).join(&branch).join("active");
+            let deployed = fs::canonicalize(&path).ok()?;
+            let metadata = fs::read_to_string(deployed.join("metadata")).unwrap_or_default();
+            let group = match kind {
+                RefKind::App => "Application",
+                RefKind::Runtime => "Runtime",
+            };
+
+            Some(FlatpakRef {
+                kind,
+                origin: origin(&deployed),
+                installation,
+                size: usage::disk_usage(&deployed),
+                runtime: keyfile_value(&metadata, group, "runtime").map(ToOwned::to_owned),
+                extension_points: extension_points(&metadata, &branch),
+                id,
+                arch,
+                branch,
+                path,
+            })
+        })
+        .collect::<Vec<_>>();
+    refs.sort_by(|a, b| (&a.id, &a.branch).cmp(&(&b.id, &b.branch)));
+    refs
+}
+
+/*
+ * The id of the flatpak app a desktop entry launches, going by its command, which flatpak always exports
+ * as "flatpak run [options] <id> [args]".
+ */
+pub fn app_id(entry: &DesktopEntry) -> Option<String> {
+    let command = ExecCommand::parse(&entry.exec).ok()?;
+    if command.program_name() != "flatpak" {
+        return None;
+    }
+    command
+        .args
+        .into_iter()
+        .skip_while(|a| a != "run")
+        .skip(1)
+        .find(|a| !a.starts_with('-'))
+}
+
+/*
+ * The deployed app a desktop entry launches. When it's installed more than once, the installation that
+ * exported the entry and the branch its command asks for win.
+ */
+pub fn find_app(entry: &DesktopEntry) -> Option<FlatpakRef> {
+    let id = app_id(entry)?;
+    let command = ExecCommand::parse(&entry.exec).ok()?;
+    let branch = command
+        .args
+        .iter()
+        .find_map(|a| a.strip_prefix("--branch="));
+
+    let mut installations = vec![Installation::User, Installation::System];
+    installations.sort_by_key(|i| {
+        !i.path()
+            .is_ok_and(|p| Path::new(&entry.full_path).starts_with(p))
+    });
+
+    let apps = installations
+        .into_iter()
+        .filter_map(|i| Some(deployments(&i.path().ok()?, i, RefKind::App, Some(&id))))
+        .flatten()
+        .collect::<Vec<_>>();
+    apps.iter()
+        .find(|a| branch.is_some_and(|b| a.branch == b))
+        .or(apps.first())
+        .cloned()
+}
+
+/*
+ * Runtimes no installed app needs anymore. A runtime is needed when an app runs on that very id, arch and
+ * branch, or when it extends (eg. with translations or graphics drivers) an app or a needed runtime in a
+ * branch its extension point accepts. So an old branch of a runtime is unused once no app runs on it, even
+ * if apps run on a newer one. This is the same idea as `flatpak uninstall --unused`, minus pinned runtimes
+ * and extensions of extensions.
+ */
+pub fn unused_runtimes(apps: &[FlatpakRef], runtimes: &[FlatpakRef]) -> Vec<FlatpakRef> {
+    let triplet = |r: &FlatpakRef| format!("{}/{}/{}", r.id, r.arch, r.branch);
+    let used = runtimes
+        .iter()
+        .filter(|r| {
+            apps.iter()
+                .any(|a| a.runtime.as_deref() == Some(&triplet(r)))
+        })
+        .collect::<Vec<_>>();
+
+    /* Extensions are named after what they extend, eg. org.gnome.Platform.Locale, even when undeclared */
+    let extends = |r: &FlatpakRef| {
+        apps.iter().chain(used.iter().copied()).any(|needed| {
+            let own = ExtensionPoint {
+                name: needed.id.clone(),
+                versions: vec![needed.branch.clone()],
+            };
+            let accepted = needed
+                .extension_points
+                .iter()
+                .chain([&own])
+                .any(|p| p.accepts(r, &needed.arch));
+            accepted
+        })
+    };
+
+    runtimes
+        .iter()
+        .filter(|r| !used.contains(r) && !extends(r))
+        .cloned()
+        .collect()
+}
+
+/*
+ * The extension points of a ref from its metadata. The versions key lists branches separated by
+ * semicolons, the version key gives a single one.
+ */
+fn extension_points(metadata: &str, branch: &str) -> Vec<ExtensionPoint> {
+    keyfile_groups(metadata)
+        .filter_map(|g| Some((g, g.strip_prefix("Extension ")?)))
+        .map(|(group, name)| {
+            let versions = keyfile_value(metadata, group, "versions")
+                .or_else(|| keyfile_value(metadata, group, "version"))
+                .map(|v| {
+                    v.split(';')
+                        .filter(|v| !v.is_empty())
+                        .map(ToOwned::to_owned)
+                        .collect()
+                })
+                .unwrap_or_else(|| vec![branch.to_owned()]);
+            ExtensionPoint {
+                name: name.to_owned(),
+                versions,
+            }
+        })
+        .collect()
+}
+
+/* Names of the directories inside a directory, empty if it can't be read. */
+fn subdirs(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().is_dir())
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/*
+ * The remote a deployment came from. It's the first field of the "deploy" file, a serialized GVariant
+ * tuple starting with a string, so it's everything up to the first NUL byte.
+ */
+fn origin(deployed: &Path) -> Option<String> {
+    let deploy = fs::read(deployed.join("deploy")).ok()?;
+    let origin = deploy.split(|b| *b == 0).next()?;
+    (!origin.is_empty()).then(|| String::from_utf8_lossy(origin).into_owned())
+}
+
+/* Group names of a GKeyFile (the format of flatpak metadata), without the brackets. */
+fn keyfile_groups(contents: &str) -> impl Iterator<Item = &str> {
+    contents
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix('[')?.strip_suffix(']'))
+}
+
+/* A value of a GKeyFile. */
+fn keyfile_value<'a>(contents: &'a str, group: &str, key: &str) -> Option<&'a str> {
+    let header = format!("[{}]", group);
+    contents
+        .lines()
+        .map(str::trim)
+        .skip_while(|l| *l != header)
+        .skip(1)
+        .take_while(|l| !l.starts_with('['))
+        .find_map(|l| {
+            let (k, v) = l.split_once('=')?;
+            (k.trim() == key).then(|| v.trim())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runtime(id: &str, branch: &str) -> FlatpakRef {
+        FlatpakRef {
+            kind: RefKind::Runtime,
+            id: id.to_owned(),
+            arch: "x86_64".to_owned(),
+            branch: branch.to_owned(),
+            origin: None,
+            installation: Installation::System,
+            path: PathBuf::new(),
+            size: 0,
+            runtime: None,
+            extension_points: Vec::new(),
+        }
+    }
+
+    fn app(id: &str, runtime: &str) -> FlatpakRef {
+        FlatpakRef {
+            kind: RefKind::App,
+            runtime: Some(runtime.to_owned()),
+            ..self::runtime(id, "stable")
+        }
+    }
+
+    fn ids(refs: &[FlatpakRef]) -> Vec<String> {
+        refs.iter()
+            .map(|r| format!("{}//{}", r.id, r.branch))
+            .collect()
+    }
+
+    #[test]
+    fn old_branches_are_unused() {
+        let apps = [app("org.gnome.Calculator", "org.gnome.Platform/x86_64/46")];
+        let runtimes = [
+            runtime("org.gnome.Platform", "46"),
+            runtime("org.gnome.Platform", "45"),
+            runtime("org.gnome.Platform.Locale", "46"),
+            runtime("org.gnome.Platform.Locale", "45"),
+        ];
+        assert_eq!(
+            ids(&unused_runtimes(&apps, &runtimes)),
+            ["org.gnome.Platform//45", "org.gnome.Platform.Locale//45"]
+        );
+    }
+
+    #[test]
+    fn extensions_follow_declared_versions() {
+        let apps = [app(
+            "org.example.App",
+            "org.freedesktop.Platform/x86_64/23.08",
+        )];
+        let mut platform = runtime("org.freedesktop.Platform", "23.08");
+        platform.extension_points = extension_points(
+            "[Runtime]\nname=org.freedesktop.Platform\n\n\
+             [Extension org.freedesktop.Platform.GL]\nversions=23.08;1.4\n",
+            "23.08",
+        );
+        let mut other_arch = runtime("org.freedesktop.Platform.GL.default", "23.08");
+        other_arch.arch = "aarch64".to_owned();
+        let runtimes = [
+            platform,
+            runtime("org.freedesktop.Platform.GL.default", "23.08"),
+            runtime("org.freedesktop.Platform.GL.nvidia-550", "1.4"),
+            runtime("org.freedesktop.Platform.GL.default", "22.08"),
+            other_arch,
+        ];
+        assert_eq!(
+            ids(&unused_runtimes(&apps, &runtimes)),
+            [
+                "org.freedesktop.Platform.GL.default//22.08",
+                "org.freedesktop.Platform.GL.default//23.08"
+            ]
+        );
+    }
+
+    #[test]
+    fn deployments_are_read_from_the_installation() {
+        let root = utils::test_dir("flatpak-deployments");
+        let commit = root.join("app/org.example.App/x86_64/stable/0123abcd");
+        fs::create_dir_all(&commit).unwrap();
+        fs::write(
+            commit.join("metadata"),
+            "[Application]\nname=org.example.App\nruntime=org.gnome.Platform/x86_64/46\n\n\
+             [Extension org.example.App.Plugin]\nversion=1\n",
+        )
+        .unwrap();
+        fs::write(commit.join("deploy"), b"flathub\0rest").unwrap();
+        std::os::unix::fs::symlink("0123abcd", commit.with_file_name("active")).unwrap();
+
+        let refs = deployments(&root, Installation::User, RefKind::App, None);
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].name(), "app/org.example.App/x86_64/stable");
+        assert_eq!(refs[0].origin.as_deref(), Some("flathub"));
+        assert_eq!(
+            refs[0].runtime.as_deref(),
+            Some("org.gnome.Platform/x86_64/46")
+        );
+        assert_eq!(
+            refs[0].extension_points,
+            [ExtensionPoint {
+                name: "org.example.App.Plugin".to_owned(),
+                versions: vec!["1".to_owned()],
+            }]
+        );
+    }
+}
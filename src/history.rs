@@ -0,0 +1,324 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::error::Error;
+use crate::icons;
+use crate::plan::{Execution, ItemKind, ItemRole, UninstallPlan};
+use crate::trash::TrashedItem;
+use crate::utils;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+/* Something that was removed while uninstalling an app, along with what is needed to put it back. */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RemovedItem {
+    pub path: PathBuf,
+    pub kind: ItemKind,
+    pub role: ItemRole,
+    /* In bytes, as measured when the plan was made. */
+    #[serde(default)]
+    pub size: u64,
+    /* Permissions and ownership at the time of removal. */
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    /* None if the item was deleted permanently. */
+    pub trashed: Option<TrashedItem>,
+    /* Set once the item is back where it was. */
+    #[serde(default)]
+    pub restored: bool,
+    /* Why the last attempt at restoring the item failed. */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restore_error: Option<String>,
+}
+
+/* A single uninstall, as stored in the history file. */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub app: String,
+    #[serde(default)]
+    pub desktop_entry: PathBuf,
+    /* When the uninstall happened, in seconds since the Unix epoch. */
+    #[serde(default)]
+    pub timestamp: i64,
+    /* The user that did it. */
+    #[serde(default)]
+    pub user: String,
+    pub items: Vec<RemovedItem>,
+    #[serde(default)]
+    pub bytes_reclaimed: u64,
+    /* Everything that went wrong during the uninstall, as shown to the user at the time. */
+    #[serde(default)]
+    pub failures: Vec<String>,
+    /* Set once every item is back, see RemovedItem::restored for the progress of a partial restore. */
+    #[serde(default)]
+    pub restored: bool,
+}
+
+/*
+ * Every uninstall we did, stored as JSON Lines (one entry per line) next to the configuration file of
+ * the app. Entries are identified by their position in the file, which never changes as entries are only
+ * ever appended.
+ */
+pub struct History {
+    path: PathBuf,
+}
+
+impl RemovedItem {
+    fn restore(&self) -> Result<(), Error> {
+        let trashed = self
+            .trashed
+            .as_ref()
+            .ok_or_else(|| Error::NotInTrash(self.path.clone()))?;
+        let err = |e| Error::CouldNotRestore(self.path.clone(), e);
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(err)?;
+        }
+        fs::rename(&trashed.trashed, &self.path).map_err(err)?;
+        fs::remove_file(&trashed.info).ok();
+
+        /* Moving things around keeps both of these, but the trash might have been tampered with in the meantime. */
+        let metadata = fs::symlink_metadata(&self.path).map_err(err)?;
+        if !metadata.is_symlink() && metadata.mode() != self.mode {
+            fs::set_permissions(&self.path, fs::Permissions::from_mode(self.mode)).map_err(err)?;
+        }
+        if metadata.uid() != self.uid || metadata.gid() != self.gid {
+            std::os::unix::fs::lchown(&self.path, Some(self.uid), Some(self.gid)).map_err(err)?;
+        }
+
+        log::info!("Restored {}", self.path.display());
+        Ok(())
+    }
+}
+
+impl HistoryEntry {
+    pub fn new(plan: &UninstallPlan, execution: &Execution) -> Self {
+        let uid = unsafe { libc::getuid() };
+        Self {
+            app: plan.app.clone(),
+            desktop_entry: plan.desktop_entry.clone(),
+            timestamp: utils::now(),
+            user: std::env::var("USER").unwrap_or_else(|_| uid.to_string()),
+            items: execution.removed.clone(),
+            bytes_reclaimed: execution.removed.iter().map(|i| i.size).sum(),
+            failures: execution.errors.iter().map(ToString::to_string).collect(),
+            restored: false,
+        }
+    }
+
+    /* Whether anything of this uninstall is still in the trash. */
+    pub fn is_restorable(&self) -> bool {
+        !self.restored
+            && self
+                .items
+                .iter()
+                .any(|i| i.trashed.is_some() && !i.restored)
+    }
+
+    /* Whether some items were restored already, but not all of them. */
+    pub fn is_partly_restored(&self) -> bool {
+        !self.restored && self.items.iter().any(|i| i.restored)
+    }
+
+    /* Paths that were removed by this uninstall but exist again, so restoring would overwrite them. */
+    pub fn conflicts(&self) -> Vec<PathBuf> {
+        self.items
+            .iter()
+            .filter(|i| i.trashed.is_some() && !i.restored && fs::symlink_metadata(&i.path).is_ok())
+            .map(|i| i.path.clone())
+            .collect()
+    }
+
+    /*
+     * Puts everything that went to the trash back where it was. Nothing at all is restored if any of it
+     * was recreated in the meantime, as we never overwrite files. Items that were deleted permanently
+     * (or restored individually through the file manager) are reported as errors, the rest is still restored.
+     * Each item remembers whether it came back, so a later attempt only goes for the ones that didn't.
+     */
+    fn restore(&mut self) -> Result<Vec<Error>, Error> {
+        let conflicts = self.conflicts();
+        if !conflicts.is_empty() {
+            return Err(Error::RestoreConflict(conflicts));
+        }
+
+        let mut errors = Vec::new();
+        for item in self.items.iter_mut().filter(|i| !i.restored) {
+            match item.restore() {
+                Ok(()) => {
+                    item.restored = true;
+                    item.restore_error = None;
+                }
+                Err(e) => {
+                    item.restore_error = Some(e.to_string());
+                    errors.push(e);
+                }
+            }
+        }
+        self.restored = self.items.iter().all(|i| i.restored);
+        icons::update_caches(self.items.iter().filter_map(|i| icons::theme_of(&i.path)));
+        Ok(errors)
+    }
+}
+
+impl History {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /* The history file of the current user, next to the configuration file. */
+    pub fn open_default() -> Result<Self, Error> {
+        let config = confy::get_configuration_file_path("LinuxAppUninstaller", None)
+            .map_err(|_| Error::HomeNotFound)?;
+        Ok(Self::new(config.with_file_name("history.jsonl")))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /* Every entry in the history, oldest first. Lines that can't be parsed are skipped. */
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>, Error> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(Error::Io(self.path.clone(), e)),
+        };
+
+        Ok(contents
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .filter_map(|l| {
+                serde_json::from_str(l)
+                    .map_err(|e| log::warn!("Skipping invalid history entry: {}", e))
+                    .ok()
+            })
+            .collect())
+    }
+
+    pub fn append(&self, entry: &HistoryEntry) -> Result<(), Error> {
+        let err = |e| Error::Io(self.path.clone(), e);
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(err)?;
+        }
+
+        let line = serde_json::to_string(entry).expect("A history entry can always be serialized");
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut f| writeln!(f, "{}", line))
+            .map_err(err)
+    }
+
+    /*
+     * Restores the entry with the given id (its position in entries()), see HistoryEntry::restore(). The
+     * entry is returned as it is saved afterwards, along with the items that couldn't be restored.
+     */
+    pub fn restore(&self, id: usize) -> Result<(HistoryEntry, Vec<Error>), Error> {
+        let mut entries = self.entries()?;
+        let entry = entries.get_mut(id).ok_or(Error::HistoryEntryNotFound(id))?;
+        if !entry.is_restorable() {
+            return Err(Error::NotRestorable(entry.app.clone()));
+        }
+
+        let errors = entry.restore()?;
+        let entry = entry.clone();
+        self.rewrite(&entries)?;
+        Ok((entry, errors))
+    }
+
+    /* Replaces the whole file, through a temporary one so that a crash never leaves a half written history. */
+    fn rewrite(&self, entries: &[HistoryEntry]) -> Result<(), Error> {
+        let err = |e| Error::Io(self.path.clone(), e);
+        let tmp = self.path.with_extension("jsonl.tmp");
+        let mut contents = String::new();
+        for entry in entries {
+            contents.push_str(
+                &serde_json::to_string(entry).expect("A history entry can always be serialized"),
+            );
+            contents.push('\n');
+        }
+
+        fs::write(&tmp, contents).map_err(err)?;
+        fs::rename(&tmp, &self.path).map_err(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trashed_item(dir: &Path, name: &str) -> RemovedItem {
+        let uid = unsafe { libc::getuid() };
+        RemovedItem {
+            path: dir.join("app").join(name),
+            kind: ItemKind::File,
+            role: ItemRole::Data,
+            size: 0,
+            mode: 0o100644,
+            uid,
+            gid: unsafe { libc::getgid() },
+            trashed: Some(TrashedItem {
+                original: dir.join("app").join(name),
+                trashed: dir.join("trash/files").join(name),
+                info: dir.join("trash/info").join(format!("{}.trashinfo", name)),
+            }),
+            restored: false,
+            restore_error: None,
+        }
+    }
+
+    #[test]
+    fn failed_items_stay_restorable() {
+        let dir = utils::test_dir("history-partial-restore");
+        fs::create_dir_all(dir.join("trash/files")).unwrap();
+        fs::write(dir.join("trash/files/kept"), "").unwrap();
+
+        let history = History::new(dir.join("history.jsonl"));
+        history
+            .append(&HistoryEntry {
+                app: "App".into(),
+                desktop_entry: PathBuf::new(),
+                timestamp: 0,
+                user: String::new(),
+                items: vec![trashed_item(&dir, "kept"), trashed_item(&dir, "lost")],
+                bytes_reclaimed: 0,
+                failures: Vec::new(),
+                restored: false,
+            })
+            .unwrap();
+
+        let (entry, errors) = history.restore(0).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(dir.join("app/kept").exists());
+        assert!(!entry.restored && entry.is_partly_restored() && entry.is_restorable());
+        assert!(entry.items[1].restore_error.is_some());
+
+        /* Once the missing item is back in the trash, only it is restored. */
+        fs::write(dir.join("trash/files/lost"), "").unwrap();
+        let (entry, errors) = history.restore(0).unwrap();
+        assert!(errors.is_empty());
+        assert!(entry.restored && !entry.is_restorable());
+        assert!(entry.items.iter().all(|i| i.restore_error.is_none()));
+        assert!(history.entries().unwrap()[0].restored);
+    }
+}
@@ -0,0 +1,148 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::dialog::Dialog;
+use appuninstaller::history::{History, HistoryEntry};
+use appuninstaller::utils::{format_size, format_timestamp};
+use gtk::{prelude::*, Align, Button, Expander, Label, Orientation, ScrolledWindow, Window};
+
+/* Lists every uninstall done so far, newest first, with a button to put each one back from the trash. */
+pub fn window() -> Window {
+    let window = Window::builder()
+        .title("Uninstall history")
+        .default_width(720)
+        .default_height(400)
+        .destroy_with_parent(true)
+        .icon_name("document-open-recent")
+        .build();
+
+    let cnt = gtk::Box::new(Orientation::Vertical, 8);
+    cnt.set_margin_start(8);
+    cnt.set_margin_end(8);
+    cnt.set_margin_top(8);
+    cnt.set_margin_bottom(8);
+
+    let entries = History::open_default().and_then(|h| h.entries());
+    match entries {
+        Ok(entries) if entries.is_empty() => {
+            cnt.append(&Label::new(Some("Nothing has been uninstalled yet.")));
+        }
+        Ok(entries) => {
+            for (id, entry) in entries.into_iter().enumerate().rev() {
+                let row = gtk::Box::new(Orientation::Horizontal, 8);
+                let label = Label::new(None);
+                let restore = Button::with_label("Restore");
+
+                label.set_markup(&format!(
+                    "<b>{}</b> ({})\nRemoved by {} on {}, {} reclaimed, {} failure(s)",
+                    gtk::glib::markup_escape_text(&entry.app),
+                    gtk::glib::markup_escape_text(&entry.desktop_entry.to_string_lossy()),
+                    gtk::glib::markup_escape_text(&entry.user),
+                    format_timestamp(entry.timestamp),
+                    format_size(entry.bytes_reclaimed),
+                    entry.failures.len()
+                ));
+                label.set_halign(Align::Start);
+                label.set_hexpand(true);
+                let details = details(&entry);
+                restore.set_valign(Align::Center);
+                show_state(&restore, &entry);
+
+                restore.connect_clicked(move |b| {
+                    match History::open_default().and_then(|h| h.restore(id)) {
+                        Ok((entry, errors)) => {
+                            show_state(b, &entry);
+                            for e in errors {
+                                Dialog::new_without_parent("Error", &e.to_string()).show();
+                            }
+                        }
+                        Err(e) => {
+                            Dialog::new_without_parent("Couldn't restore", &e.to_string()).show()
+                        }
+                    }
+                });
+
+                row.append(&label);
+                row.append(&restore);
+                cnt.append(&row);
+                cnt.append(&details);
+            }
+        }
+        Err(e) => {
+            cnt.append(&Label::new(Some(&format!(
+                "Couldn't read the history: {}",
+                e
+            ))));
+        }
+    }
+
+    window.set_child(Some(&ScrolledWindow::builder().child(&cnt).build()));
+    window
+}
+
+/* Labels the restore button of an entry after how much of it was restored already. */
+fn show_state(button: &Button, entry: &HistoryEntry) {
+    button.set_sensitive(entry.is_restorable());
+    if entry.restored {
+        button.set_label("Restored");
+    } else if entry.is_partly_restored() {
+        button.set_label(if entry.is_restorable() {
+            "Restore the rest"
+        } else {
+            "Partly restored"
+        });
+    }
+}
+
+/* Every path an uninstall removed and everything that went wrong, collapsed by default. */
+fn details(entry: &HistoryEntry) -> Expander {
+    let mut text = entry
+        .items
+        .iter()
+        .map(|i| {
+            format!(
+                "{} ({}{})",
+                i.path.display(),
+                format_size(i.size),
+                if i.restored {
+                    ", restored"
+                } else if i.trashed.is_some() {
+                    ", in trash"
+                } else {
+                    ", deleted"
+                }
+            )
+        })
+        .collect::<Vec<_>>();
+    text.extend(
+        entry
+            .items
+            .iter()
+            .filter_map(|i| Some(format!("Couldn't restore: {}", i.restore_error.as_ref()?))),
+    );
+    text.extend(entry.failures.iter().map(|f| format!("Failed: {}", f)));
+
+    let label = Label::new(Some(&text.join("\n")));
+    label.set_halign(Align::Start);
+    label.set_selectable(true);
+    label.set_margin_start(16);
+
+    let expander = Expander::new(Some("Details"));
+    expander.set_child(Some(&label));
+    expander
+}
@@ -0,0 +1,297 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::desktop::DesktopEntry;
+use crate::utils;
+use rayon::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/* The formats icons come in, see the Icon Theme spec. */
+const ICON_EXTENSIONS: [&str; 4] = ["png", "svg", "svgz", "xpm"];
+
+/* Themes are laid out as <theme>/<size>/<context>/<icon>, or the other way around, with some slack. */
+const MAX_THEME_DEPTH: usize = 4;
+
+/* The tools that regenerate icon-theme.cache, GTK 3 first as GTK 4 ones aren't always around. */
+const CACHE_UPDATERS: [&str; 2] = ["gtk-update-icon-cache", "gtk4-update-icon-cache"];
+
+/* Directories icon themes are installed in: ~/.icons and the icons directory of every XDG data directory. */
+pub fn theme_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(home) = utils::home_dir() {
+        dirs.push(home.join(".icons"));
+    }
+    if let Ok(data_home) = utils::data_home() {
+        dirs.push(data_home.join("icons"));
+    }
+    dirs.extend(utils::data_dirs().into_iter().map(|d| d.join("icons")));
+    dirs
+}
+
+/* Directories unthemed icons are installed in. */
+pub fn pixmap_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(data_home) = utils::data_home() {
+        dirs.push(data_home.join("pixmaps"));
+    }
+    dirs.extend(utils::data_dirs().into_iter().map(|d| d.join("pixmaps")));
+    dirs
+}
+
+/* Every icon theme installed in the given directories, eg. .../icons/hicolor and .../icons/Papirus. */
+fn themes_in(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    dirs.iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| e.path())
+        .collect()
+}
+
+/*
+ * Every file of an icon an app installed, as given by the Icon key of its desktop entry: the file itself
+ * if it is a path, or else the icon of that name in every size of every theme and in the pixmaps
+ * directories. Apps install into hicolor, but plenty put icons into Papirus, Adwaita and the like too.
+ */
+pub fn find_icon_files(icon: &str) -> Vec<PathBuf> {
+    find_icon_files_in(icon, &themes_in(&theme_dirs()), &pixmap_dirs())
+}
+
+fn find_icon_files_in(icon: &str, themes: &[PathBuf], pixmaps: &[PathBuf]) -> Vec<PathBuf> {
+    let path = Path::new(icon);
+    if path.is_absolute() {
+        return path
+            .is_file()
+            .then(|| path.to_path_buf())
+            .into_iter()
+            .collect();
+    }
+    let name = icon_name(icon);
+    if name.is_empty() || name.contains('/') {
+        return Vec::new();
+    }
+
+    let mut files = themes
+        .par_iter()
+        .flat_map(|theme| {
+            let mut found = Vec::new();
+            find_named(theme, name, MAX_THEME_DEPTH, &mut found);
+            found
+        })
+        .collect::<Vec<_>>();
+    for dir in pixmaps {
+        find_named(dir, name, 0, &mut files);
+    }
+    files.sort();
+    files.dedup();
+    files
+}
+
+/*
+ * The files of the icon of an entry that no other entry uses. An icon other entries refer to by name is
+ * kept as a whole, one they refer to by path just that file.
+ */
+pub fn unshared_icon_files(entry: &DesktopEntry, entries: &[DesktopEntry]) -> Vec<PathBuf> {
+    unshared_icon_files_in(entry, entries, &themes_in(&theme_dirs()), &pixmap_dirs())
+}
+
+fn unshared_icon_files_in(
+    entry: &DesktopEntry,
+    entries: &[DesktopEntry],
+    themes: &[PathBuf],
+    pixmaps: &[PathBuf],
+) -> Vec<PathBuf> {
+    let Some(icon) = entry.icon_path.as_deref().filter(|i| !i.is_empty()) else {
+        return Vec::new();
+    };
+    let others = entries
+        .iter()
+        .filter(|e| e.id != entry.id)
+        .filter_map(|e| e.icon_path.as_deref())
+        .filter(|i| !i.is_empty())
+        .collect::<Vec<_>>();
+    if others
+        .iter()
+        .any(|other| icon_name(other) == icon_name(icon))
+    {
+        log::info!("Keeping icon {}, other desktop entries use it", icon);
+        return Vec::new();
+    }
+
+    let mut files = find_icon_files_in(icon, themes, pixmaps);
+    files.retain(|file| !others.iter().any(|other| Path::new(other) == file));
+    files
+}
+
+/* The theme an icon file belongs to, eg. ~/.local/share/icons/hicolor for .../hicolor/48x48/apps/foo.png. */
+pub fn theme_of(path: &Path) -> Option<PathBuf> {
+    theme_dirs().into_iter().find_map(|dir| {
+        let theme = path.strip_prefix(&dir).ok()?.components().next()?;
+        let theme = dir.join(theme);
+        (theme != path).then_some(theme)
+    })
+}
+
+/*
+ * Regenerates the icon-theme.cache of every given theme that has one, as GTK would otherwise keep looking
+ * for the removed (or restored) icons in a stale cache. Failures, eg. for lack of permissions on system
+ * themes, are only logged.
+ */
+pub fn update_caches(themes: impl IntoIterator<Item = PathBuf>) {
+    let mut updated = Vec::new();
+    for theme in themes {
+        if updated.contains(&theme) || !theme.join("icon-theme.cache").is_file() {
+            continue;
+        }
+        let status = CACHE_UPDATERS.iter().find_map(|updater| {
+            Command::new(updater)
+                .args(["--force", "--ignore-theme-index", "--quiet"])
+                .arg(&theme)
+                .status()
+                .ok()
+        });
+        match status {
+            Some(status) if status.success() => {
+                log::info!("Updated the icon cache of {}", theme.display())
+            }
+            Some(status) => log::warn!(
+                "Couldn't update the icon cache of {} ({})",
+                theme.display(),
+                status
+            ),
+            None => log::warn!(
+                "Couldn't update the icon cache of {}, none of {} is installed",
+                theme.display(),
+                CACHE_UPDATERS.join(", ")
+            ),
+        }
+        updated.push(theme);
+    }
+}
+
+/* The Icon key without the extension some entries put in it, against the spec. */
+fn icon_name(icon: &str) -> &str {
+    match icon.rsplit_once('.') {
+        Some((name, extension)) if ICON_EXTENSIONS.contains(&extension) => name,
+        _ => icon,
+    }
+}
+
+/* Files named after an icon under a directory, looking at most `depth` directories deep. */
+fn find_named(dir: &Path, name: &str, depth: usize, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            if depth > 0 {
+                find_named(&path, name, depth - 1, found);
+            }
+        } else if path.file_stem().is_some_and(|s| s == name)
+            && path
+                .extension()
+                .is_some_and(|e| ICON_EXTENSIONS.iter().any(|x| e == *x))
+        {
+            found.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* Creates empty icon files under a fixture directory. */
+    fn icon_tree(name: &str, files: &[&str]) -> PathBuf {
+        let dir = utils::test_dir(name);
+        for file in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        dir
+    }
+
+    fn entry(id: &str, icon: &str) -> DesktopEntry {
+        DesktopEntry::new(
+            id.to_owned(),
+            id.to_owned(),
+            Some(icon.to_owned()),
+            String::new(),
+            format!("/usr/share/applications/{}.desktop", id),
+        )
+    }
+
+    #[test]
+    fn every_theme_and_pixmaps_are_searched() {
+        let dir = icon_tree(
+            "icons-themes",
+            &[
+                "icons/hicolor/48x48/apps/foo.png",
+                "icons/hicolor/scalable/apps/foo.svg",
+                "icons/hicolor/48x48/apps/foobar.png",
+                "icons/Papirus/64x64/apps/foo.svg",
+                "icons/Adwaita/scalable/apps/foo.svg",
+                "pixmaps/foo.xpm",
+            ],
+        );
+
+        let themes = themes_in(&[dir.join("icons"), dir.join("missing")]);
+        assert_eq!(themes.len(), 3);
+        let found = find_icon_files_in("foo.png", &themes, &[dir.join("pixmaps")]);
+        assert_eq!(
+            found,
+            [
+                dir.join("icons/Adwaita/scalable/apps/foo.svg"),
+                dir.join("icons/Papirus/64x64/apps/foo.svg"),
+                dir.join("icons/hicolor/48x48/apps/foo.png"),
+                dir.join("icons/hicolor/scalable/apps/foo.svg"),
+                dir.join("pixmaps/foo.xpm"),
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_icons_are_kept() {
+        let dir = icon_tree(
+            "icons-shared",
+            &[
+                "icons/hicolor/48x48/apps/foo.png",
+                "icons/Papirus/64x64/apps/foo.svg",
+            ],
+        );
+        let themes = themes_in(&[dir.join("icons")]);
+        let foo = entry("foo", "foo");
+
+        /* Another entry showing the same icon by name keeps all of it */
+        let entries = [foo.clone(), entry("foo-private", "foo.png")];
+        assert!(unshared_icon_files_in(&foo, &entries, &themes, &[]).is_empty());
+
+        /* One pointing at one of its files keeps just that file */
+        let papirus = dir.join("icons/Papirus/64x64/apps/foo.svg");
+        let entries = [foo.clone(), entry("bar", &papirus.to_string_lossy())];
+        assert_eq!(
+            unshared_icon_files_in(&foo, &entries, &themes, &[]),
+            [dir.join("icons/hicolor/48x48/apps/foo.png")]
+        );
+    }
+}
@@ -0,0 +1,185 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::desktop::DesktopEntry;
+use crate::exec;
+use crate::purge::AppPurger;
+use crate::safety;
+use crate::utils;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/*
+ * Directories self-contained apps are unpacked into, one directory per app, eg. /opt/<app> or
+ * ~/apps/<app>-1.2. Relative ones are in the home directory.
+ */
+const INSTALL_PARENTS: [&str; 10] = [
+    "/opt",
+    "/usr/local/opt",
+    "apps",
+    "Apps",
+    "opt",
+    ".local/opt",
+    "Applications",
+    "programs",
+    "Programs",
+    "software",
+];
+
+/* Where Windows programs go inside a Wine prefix. */
+const WINE_PARENTS: [&str; 2] = ["Program Files", "Program Files (x86)"];
+
+/* Subdirectories of an install root, which never are a root of their own. */
+const LAYOUT_DIRS: [&str; 10] = [
+    "bin", "sbin", "lib", "lib32", "lib64", "libexec", "share", "etc", "usr", "jre",
+];
+
+/* Directories in the home directory that hold the user's files, never an install root. */
+const USER_DIRS: [&str; 8] = [
+    "Desktop",
+    "Documents",
+    "Downloads",
+    "Music",
+    "Pictures",
+    "Public",
+    "Templates",
+    "Videos",
+];
+
+/*
+ * The directory a manually installed app lives in as a whole, so it can be removed together instead of
+ * just the executable. It is found from where the real program is (after following symlinks and wrapper
+ * scripts, see exec::follow_launcher()), or failing that from the Path key of the desktop entry if the
+ * program is inside that directory. Without a program, nothing is known to be in there, so there is no
+ * install root. AppImages are single files and have no install root.
+ */
+pub fn find_root(entry: &DesktopEntry) -> Option<PathBuf> {
+    if entry.appimage.is_some() {
+        return None;
+    }
+    let home = utils::home_dir().ok()?;
+    let program = AppPurger::find_exec(entry)
+        .map(|exec| exec::follow_launcher(&exec).pop().unwrap_or(exec))?;
+
+    let root = root_of(&program, &home)
+        .or_else(|| working_dir_root(Path::new(entry.working_dir.as_deref()?), &program))?;
+    is_removable(&root, &home).then_some(root)
+}
+
+/* The install root given by the Path key, which only counts when the program is inside it. */
+fn working_dir_root(dir: &Path, program: &Path) -> Option<PathBuf> {
+    if !dir.is_absolute() || !dir.is_dir() || !program.starts_with(dir) {
+        return None;
+    }
+    /* Path often names the bin directory of the app rather than the app itself */
+    match dir.file_name()?.to_str() {
+        Some(name) if LAYOUT_DIRS.contains(&name) => dir.parent().map(Path::to_path_buf),
+        _ => Some(dir.to_path_buf()),
+    }
+}
+
+/* The install root of a program that lives in one of the INSTALL_PARENTS or in a Wine prefix. */
+fn root_of(program: &Path, home: &Path) -> Option<PathBuf> {
+    let parents = INSTALL_PARENTS
+        .iter()
+        .map(|p| home.join(p))
+        .collect::<Vec<_>>();
+    let root = program.ancestors().skip(1).find(|dir| {
+        let Some(parent) = dir.parent() else {
+            return false;
+        };
+        let wine = parent
+            .file_name()
+            .is_some_and(|n| WINE_PARENTS.iter().any(|w| n == *w))
+            && parent.parent().is_some_and(|p| p.ends_with("drive_c"));
+        wine || parents.iter().any(|p| p == parent)
+    })?;
+
+    /*
+     * Vendors often put their apps one level deeper, eg. /opt/google/chrome next to /opt/google/earth,
+     * in which case only the directory of this app goes.
+     */
+    let child = program
+        .strip_prefix(root)
+        .ok()?
+        .components()
+        .next()?
+        .as_os_str();
+    let nested = root.join(child);
+    let only_dirs = fs::read_dir(root)
+        .ok()?
+        .flatten()
+        .all(|e| e.file_type().is_ok_and(|t| t.is_dir()));
+    if only_dirs && nested.is_dir() && !LAYOUT_DIRS.iter().any(|d| child == *d) {
+        Some(nested)
+    } else {
+        Some(root.to_path_buf())
+    }
+}
+
+/* Whether removing the whole directory can't take anything but the app with it. */
+fn is_removable(root: &Path, home: &Path) -> bool {
+    let user_dir = root.parent() == Some(home)
+        && root
+            .file_name()
+            .is_some_and(|n| USER_DIRS.iter().any(|d| n == *d));
+    let parent = INSTALL_PARENTS
+        .iter()
+        .any(|p| home.join(p).starts_with(root));
+    !user_dir && !parent && safety::check(root).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn working_dir_needs_the_program_inside() {
+        let dir = utils::test_dir("installroot-working-dir");
+        fs::create_dir_all(dir.join("game/bin")).unwrap();
+        fs::create_dir_all(dir.join("Documents/work")).unwrap();
+
+        let program = dir.join("game/bin/game");
+        assert_eq!(
+            working_dir_root(&dir.join("game/bin"), &program),
+            Some(dir.join("game"))
+        );
+        assert_eq!(
+            working_dir_root(&dir.join("Documents/work"), &program),
+            None
+        );
+    }
+
+    #[test]
+    fn vendor_dirs_keep_other_apps() {
+        let home = utils::test_dir("installroot-vendor");
+        fs::create_dir_all(home.join("opt/google/chrome")).unwrap();
+        fs::create_dir_all(home.join("opt/google/earth")).unwrap();
+        fs::create_dir_all(home.join("apps/foo-1.2/bin")).unwrap();
+
+        assert_eq!(
+            root_of(&home.join("opt/google/chrome/chrome"), &home),
+            Some(home.join("opt/google/chrome"))
+        );
+        assert_eq!(
+            root_of(&home.join("apps/foo-1.2/bin/foo"), &home),
+            Some(home.join("apps/foo-1.2"))
+        );
+        assert_eq!(root_of(&home.join("bin/foo"), &home), None);
+    }
+}
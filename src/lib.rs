@@ -0,0 +1,40 @@
+#![warn(clippy::all)]
+//! The uninstall engine behind App Uninstaller. Everything in here works without a display,
+//! so it can be used by the GTK frontend as well as by any tool that links against it.
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+pub mod appimage;
+pub mod desktop;
+pub mod error;
+pub mod exec;
+pub mod flatpak;
+pub mod history;
+pub mod icons;
+pub mod installroot;
+pub mod pkgdb;
+pub mod pkgmgr;
+pub mod plan;
+pub mod purge;
+mod rpmdb;
+pub mod safety;
+pub mod snap;
+mod squashfs;
+pub mod trash;
+pub mod usage;
+pub mod utils;
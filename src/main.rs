@@ -17,13 +17,15 @@
 ///  
 /// You should have received a copy of the GNU General Public License  
 /// along with this program.  If not, see <https://www.gnu.org/licenses/>.
-mod desktop;
 mod dialog;
-mod error;
+mod historyview;
 mod preferences;
-mod purge;
-mod utils;
+mod runtimesview;
+mod view;
 
+use appuninstaller::desktop;
+use appuninstaller::pkgdb::PackageDb;
+use appuninstaller::safety;
 use dialog::Dialog;
 use gtk::gdk::{prelude::*, Display};
 use gtk::gio::SimpleAction;
@@ -34,14 +36,15 @@ use gtk::{Application, Builder};
 #[allow(unused_imports)]
 use log::{error, info, warn};
 use preferences::Preferences;
-use utils::{isolate_exec, omit_dir_from_cmd};
+use view::EntryView;
 
 const STARTUP_MSG: &str = r#"This application is meant to be used for very specific cases, like apps built and installed manually.
 It is NOT a replacement for `apt`, `pacman` or any other package manager. In fact, it can cause problems if you use this app to uninstall
 apps installed through a package manager.
 
-You are assumed to be responsible enough to understand that DATA WILL BE LOST FOREVER and you CANNOT reverse deletion. Please make sure
-you do not just blindly run "Delete app" on everything to free space.
+Removed files are moved to the trash, so you can still get them back from your file manager until you empty it. If you enable permanent
+deletion in the preferences, DATA WILL BE LOST FOREVER and you CANNOT reverse deletion. Either way, please make sure you do not just blindly
+run "Delete app" on everything to free space.
 
 Final warning: Make sure you know what you are doing. The creator is not liable or responsible in any way if something doesn't go the way you
 expected, you accidentally uninstall something you shouldn't or dragons jump out of your computer and try to bite you. Again, you should probably not run this
@@ -54,12 +57,19 @@ fn main() -> glib::ExitCode {
 
     env_logger::init();
 
+    /* Reading the package databases takes a moment, so do it while the UI starts instead of on the first click. */
+    std::thread::spawn(|| {
+        PackageDb::system();
+    });
+
     let app = Application::builder()
         .application_id("org.sfd.LinuxAppUninstaller")
         .build();
 
     app.connect_activate(move |app| {
         let pref = Preferences::load();
+        safety::set_protected(pref.protected_paths.clone());
+        let show_hidden = pref.show_hidden;
         let builder = Builder::from_string(include_str!("../ui/window.xml"));
         let provider = CssProvider::new();
         provider.load_from_data(include_str!("../ui/style.css"));
@@ -77,12 +87,17 @@ fn main() -> glib::ExitCode {
         unsafe {
             if libc::getuid() == 0 {
                 warn!("This program should not be run as root");
-                builder.object::<gtk::Box>("warn-root").unwrap().set_visible(true);
+                builder
+                    .object::<gtk::Box>("warn-root")
+                    .unwrap()
+                    .set_visible(true);
             }
         }
         window.set_maximized(pref.fullscreen);
 
         let prefaction = SimpleAction::new("preferences", None);
+        let historyaction = SimpleAction::new("history", None);
+        let runtimesaction = SimpleAction::new("runtimes", None);
         let quitaction = SimpleAction::new("quit", None);
         let windowclone = window.clone();
         let aboutaction = SimpleAction::new("about", None);
@@ -100,6 +115,8 @@ fn main() -> glib::ExitCode {
                 .present();
         });
         app.add_action(&prefaction);
+        app.add_action(&historyaction);
+        app.add_action(&runtimesaction);
         app.add_action(&quitaction);
         app.add_action(&aboutaction);
 
@@ -111,6 +128,14 @@ fn main() -> glib::ExitCode {
             pref.window().present();
         });
 
+        historyaction.connect_activate(|_, _| {
+            historyview::window().present();
+        });
+
+        runtimesaction.connect_activate(|_, _| {
+            runtimesview::window().present();
+        });
+
         let applist: gtk::Box = builder.object("applist").unwrap_or_else(|| {
             warn!("Failed to retrieve a UI element from the descriptor file");
             std::process::exit(-1);
@@ -125,20 +150,8 @@ fn main() -> glib::ExitCode {
 
         apps.sort_by(|a, b| a.name.cmp(&b.name));
         for a in apps {
-            let blacklisted_execs = [
-                "flatpak",
-                "xdg-open",
-                "systemsettings",
-                "cinnamon-settings",
-                "gamemoderun",
-                "gapplication",
-                "java",
-                "kcmshell6"
-            ];
-            if blacklisted_execs.iter().any(|x| {
-                omit_dir_from_cmd((*x.to_owned()).to_string()) == isolate_exec(a.exec.clone())
-            }) {
-                log::warn!("Skipping application \"{}\"", a.name);
+            if let Some(reason) = a.hidden.as_ref().filter(|_| !show_hidden) {
+                log::info!("Skipping application \"{}\" as {}", a.name, reason);
                 continue;
             }
 
@@ -0,0 +1,340 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::desktop::DesktopEntry;
+use crate::flatpak;
+use crate::purge::AppPurger;
+use crate::rpmdb;
+use crate::snap;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/*
+ * Finds out which package of the system package manager owns a file, straight from the package databases
+ * on disk. Nothing is executed, so any directory laid out like a root filesystem can be used, which makes
+ * this easy to test against fixture trees.
+ */
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    Dpkg,
+    Pacman,
+    Rpm,
+}
+
+impl fmt::Display for PackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Dpkg => "dpkg",
+            Self::Pacman => "pacman",
+            Self::Rpm => "rpm",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PackageOwner {
+    pub manager: PackageManager,
+    pub package: String,
+}
+
+impl fmt::Display for PackageOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.package, self.manager)
+    }
+}
+
+/* Every file known to the package databases under a root directory, with the package that owns it. */
+pub struct PackageDb {
+    root: PathBuf,
+    /* Ordered so that everything under a directory comes right after it, see owned_under(). */
+    owners: BTreeMap<PathBuf, PackageOwner>,
+}
+
+impl PackageDb {
+    /* Reads the databases of every supported package manager found under `root`. */
+    pub fn load(root: &Path) -> Self {
+        let mut owners = BTreeMap::new();
+        for (files, owner) in dpkg_files(root)
+            .into_iter()
+            .chain(pacman_files(root))
+            .chain(rpm_files(root))
+        {
+            for file in files {
+                owners.entry(file).or_insert_with(|| owner.clone());
+            }
+        }
+
+        log::info!("Loaded {} files from the package databases", owners.len());
+        Self {
+            root: root.to_path_buf(),
+            owners,
+        }
+    }
+
+    /* The databases of the running system. They are only read once, the first time this is called. */
+    pub fn system() -> &'static Self {
+        static SYSTEM: OnceLock<PackageDb> = OnceLock::new();
+        SYSTEM.get_or_init(|| Self::load(Path::new("/")))
+    }
+
+    /*
+     * The package that owns a path, if any. The path is given as seen from the root directory, eg.
+     * "/usr/bin/foo". Aliases created by a merged /usr (like /bin being /usr/bin) are taken into account,
+     * and so are symlinks when the root is the real one.
+     */
+    pub fn owner(&self, path: &Path) -> Option<&PackageOwner> {
+        self.aliases(path).iter().find_map(|c| self.owners.get(c))
+    }
+
+    /*
+     * A file or directory under a directory that belongs to a package, with its owner. A directory the
+     * package databases don't list can still hold the files of a package.
+     */
+    pub fn owned_under(&self, dir: &Path) -> Option<(&Path, &PackageOwner)> {
+        self.aliases(dir).iter().find_map(|dir| {
+            self.owners
+                .range::<Path, _>((Bound::Excluded(dir.as_path()), Bound::Unbounded))
+                .next()
+                .filter(|(path, _)| path.starts_with(dir))
+                .map(|(path, owner)| (path.as_path(), owner))
+        })
+    }
+
+    /* The path along with the other paths it is known as, see owner(). */
+    fn aliases(&self, path: &Path) -> Vec<PathBuf> {
+        let mut candidates = vec![path.to_path_buf()];
+        if self.root == Path::new("/") {
+            if let Ok(real) = fs::canonicalize(path) {
+                candidates.push(real);
+            }
+        }
+
+        let merged = ["bin", "sbin", "lib", "lib32", "lib64"];
+        for path in candidates.clone() {
+            if let Ok(rest) = path.strip_prefix("/usr") {
+                if merged.iter().any(|d| rest.starts_with(d)) {
+                    candidates.push(Path::new("/").join(rest));
+                }
+            } else if merged
+                .iter()
+                .any(|d| path.strip_prefix("/").is_ok_and(|p| p.starts_with(d)))
+            {
+                candidates.push(Path::new("/usr").join(path.strip_prefix("/").unwrap_or(&path)));
+            }
+        }
+
+        candidates
+    }
+
+    /*
+     * The package that installed an app, going by its desktop entry (or the ones it shadows, when it is a
+     * user override) first and its executable second.
+     * The executable of flatpak and snap apps is flatpak or snap itself, which says nothing about who
+     * installed the app.
+     */
+    pub fn entry_owner(&self, entry: &DesktopEntry) -> Option<&PackageOwner> {
+        if flatpak::app_id(entry).is_some() || snap::snap_name(&self.root, entry).is_some() {
+            return self.owner(Path::new(&entry.full_path));
+        }
+        let owner = [&entry.full_path]
+            .into_iter()
+            .chain(&entry.shadowed)
+            .find_map(|path| self.owner(Path::new(path)));
+        owner.or_else(|| {
+            AppPurger::find_exec(entry)
+                .as_deref()
+                .and_then(|exec| self.owner(exec))
+        })
+    }
+}
+
+/* dpkg keeps a list of the files of every package in /var/lib/dpkg/info/<package>[:<arch>].list */
+fn dpkg_files(root: &Path) -> Vec<(Vec<PathBuf>, PackageOwner)> {
+    let Ok(dir) = fs::read_dir(root.join("var/lib/dpkg/info")) else {
+        return Vec::new();
+    };
+
+    dir.flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "list"))
+        .collect::<Vec<_>>()
+        .par_iter()
+        .filter_map(|list| {
+            let stem = list.file_stem()?.to_string_lossy();
+            let package = stem.split(':').next().unwrap_or(&stem).to_owned();
+            let files = fs::read_to_string(list)
+                .ok()?
+                .lines()
+                .filter(|l| l.starts_with('/') && *l != "/.")
+                .map(PathBuf::from)
+                .collect();
+            Some((
+                files,
+                PackageOwner {
+                    manager: PackageManager::Dpkg,
+                    package,
+                },
+            ))
+        })
+        .collect()
+}
+
+/*
+ * pacman keeps every package in /var/lib/pacman/local/<name>-<version>-<release>/, with its name in the
+ * "desc" file and its files (relative to the root) in the "files" file, each under a %SECTION% header.
+ */
+fn pacman_files(root: &Path) -> Vec<(Vec<PathBuf>, PackageOwner)> {
+    let Ok(dir) = fs::read_dir(root.join("var/lib/pacman/local")) else {
+        return Vec::new();
+    };
+
+    dir.flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect::<Vec<_>>()
+        .par_iter()
+        .filter_map(|pkg| {
+            let desc = fs::read_to_string(pkg.join("desc")).ok()?;
+            let package = pacman_section(&desc, "NAME").next()?.to_owned();
+            let files = fs::read_to_string(pkg.join("files")).ok()?;
+            let files = pacman_section(&files, "FILES")
+                .map(|f| Path::new("/").join(f.trim_end_matches('/')))
+                .collect();
+            Some((
+                files,
+                PackageOwner {
+                    manager: PackageManager::Pacman,
+                    package,
+                },
+            ))
+        })
+        .collect()
+}
+
+/* The lines of a %SECTION% of a pacman database file, up to the empty line that ends it. */
+fn pacman_section<'a>(contents: &'a str, section: &str) -> impl Iterator<Item = &'a str> {
+    let header = format!("%{}%", section);
+    contents
+        .lines()
+        .skip_while(move |l| *l != header)
+        .skip(1)
+        .take_while(|l| !l.is_empty())
+}
+
+/* rpm keeps everything in /var/lib/rpm/rpmdb.sqlite (or /usr/lib/sysimage/rpm on newer distributions). */
+fn rpm_files(root: &Path) -> Vec<(Vec<PathBuf>, PackageOwner)> {
+    [
+        "usr/lib/sysimage/rpm/rpmdb.sqlite",
+        "var/lib/rpm/rpmdb.sqlite",
+    ]
+    .iter()
+    .map(|db| root.join(db))
+    .find(|db| db.exists())
+    .and_then(|db| {
+        rpmdb::read_packages(&db).or_else(|| {
+            log::warn!("Couldn't read the rpm database at {}", db.display());
+            None
+        })
+    })
+    .unwrap_or_default()
+    .into_iter()
+    .map(|pkg| {
+        (
+            pkg.files,
+            PackageOwner {
+                manager: PackageManager::Rpm,
+                package: pkg.name,
+            },
+        )
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    fn write(path: PathBuf, contents: impl AsRef<[u8]>) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn owner(db: &PackageDb, path: &str) -> Option<String> {
+        db.owner(Path::new(path)).map(|o| o.to_string())
+    }
+
+    #[test]
+    fn files_are_owned_by_their_package() {
+        let root = utils::test_dir("pkgdb-owners");
+        write(
+            root.join("var/lib/dpkg/info/foo:amd64.list"),
+            "/.\n/usr\n/usr/bin\n/usr/bin/foo\n",
+        );
+        write(
+            root.join("var/lib/pacman/local/bar-1.0-1/desc"),
+            "%NAME%\nbar\n\n%VERSION%\n1.0-1\n",
+        );
+        write(
+            root.join("var/lib/pacman/local/bar-1.0-1/files"),
+            "%FILES%\nusr/\nusr/share/bar/\nusr/share/bar/data\n\n%BACKUP%\netc/bar.conf\n",
+        );
+        write(
+            root.join("usr/lib/sysimage/rpm/rpmdb.sqlite"),
+            rpmdb::test_database(&[("baz", &["/usr/bin/baz"])]),
+        );
+
+        let db = PackageDb::load(&root);
+        assert_eq!(owner(&db, "/usr/bin/foo").as_deref(), Some("foo (dpkg)"));
+        /* /bin is the same as /usr/bin on a merged /usr. */
+        assert_eq!(owner(&db, "/bin/foo").as_deref(), Some("foo (dpkg)"));
+        assert_eq!(
+            owner(&db, "/usr/share/bar").as_deref(),
+            Some("bar (pacman)")
+        );
+        assert_eq!(
+            owner(&db, "/usr/share/bar/data").as_deref(),
+            Some("bar (pacman)")
+        );
+        assert_eq!(owner(&db, "/etc/bar.conf"), None);
+        assert_eq!(owner(&db, "/usr/bin/baz").as_deref(), Some("baz (rpm)"));
+        assert_eq!(owner(&db, "/usr/bin/qux"), None);
+    }
+
+    #[test]
+    fn corrupt_rpm_databases_are_skipped() {
+        let root = utils::test_dir("pkgdb-corrupt-rpm");
+        let data = rpmdb::test_database(&[("baz", &["/usr/bin/baz"])]);
+        write(
+            root.join("var/lib/rpm/rpmdb.sqlite"),
+            &data[..data.len() / 2],
+        );
+        write(root.join("var/lib/dpkg/info/foo.list"), "/usr/bin/foo\n");
+
+        let db = PackageDb::load(&root);
+        assert_eq!(owner(&db, "/usr/bin/baz"), None);
+        assert_eq!(owner(&db, "/usr/bin/foo").as_deref(), Some("foo (dpkg)"));
+    }
+}
@@ -0,0 +1,484 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::error::Error;
+use crate::pkgdb::{PackageManager, PackageOwner};
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+
+/*
+ * Removing an app that belongs to a package is the job of the package manager, so instead of deleting its
+ * files ourselves we build the matching command and run it (through pkexec, as it needs root).
+ * Removing a package can take others that depend on it along, so a dry run comes first to find out which
+ * packages go, and the user is shown them before anything is removed.
+ */
+pub trait PackageBackend {
+    /* Name of the backend as shown to the user, eg. "apt". */
+    fn name(&self) -> &str;
+    /* Which package databases this backend manages. */
+    fn manages(&self) -> PackageManager;
+    /* The command that removes the package, without any privilege escalation. */
+    fn remove_command(&self, package: &str) -> Vec<String>;
+    /* A command that shows what removing the package would do, without removing anything. */
+    fn dry_run_command(&self, package: &str) -> Vec<String>;
+    /* Whether the dry run needs root as well, some package managers won't even plan a removal without it. */
+    fn dry_run_needs_root(&self) -> bool {
+        false
+    }
+    /* The packages that would be removed, out of the output of the dry run. */
+    fn parse_dry_run(&self, output: &[String]) -> Vec<String>;
+    fn is_available(&self) -> bool {
+        self.remove_command("")
+            .first()
+            .is_some_and(|program| which::which(program).is_ok())
+    }
+}
+
+pub struct Apt;
+pub struct Dnf;
+pub struct Zypper;
+pub struct Pacman;
+
+impl PackageBackend for Apt {
+    fn name(&self) -> &str {
+        "apt"
+    }
+
+    fn manages(&self) -> PackageManager {
+        PackageManager::Dpkg
+    }
+
+    fn remove_command(&self, package: &str) -> Vec<String> {
+        vec!["apt".into(), "remove".into(), "-y".into(), package.into()]
+    }
+
+    fn dry_run_command(&self, package: &str) -> Vec<String> {
+        vec![
+            "apt-get".into(),
+            "-s".into(),
+            "remove".into(),
+            package.into(),
+        ]
+    }
+
+    /* Every package that goes is listed as "Remv <package> [<version>]". */
+    fn parse_dry_run(&self, output: &[String]) -> Vec<String> {
+        output
+            .iter()
+            .filter_map(|line| line.strip_prefix("Remv ")?.split_whitespace().next())
+            .map(str::to_owned)
+            .collect()
+    }
+}
+
+impl PackageBackend for Dnf {
+    fn name(&self) -> &str {
+        "dnf"
+    }
+
+    fn manages(&self) -> PackageManager {
+        PackageManager::Rpm
+    }
+
+    fn remove_command(&self, package: &str) -> Vec<String> {
+        vec!["dnf".into(), "remove".into(), "-y".into(), package.into()]
+    }
+
+    fn dry_run_command(&self, package: &str) -> Vec<String> {
+        vec![
+            "dnf".into(),
+            "remove".into(),
+            "--assumeno".into(),
+            package.into(),
+        ]
+    }
+
+    fn dry_run_needs_root(&self) -> bool {
+        true
+    }
+
+    /* The packages are listed one per row, under "Removing:" and "Removing dependent packages:" and such. */
+    fn parse_dry_run(&self, output: &[String]) -> Vec<String> {
+        indented_under(output, |header| header.starts_with("Removing"))
+            .filter_map(|line| line.split_whitespace().next())
+            .map(str::to_owned)
+            .collect()
+    }
+}
+
+impl PackageBackend for Zypper {
+    fn name(&self) -> &str {
+        "zypper"
+    }
+
+    fn manages(&self) -> PackageManager {
+        PackageManager::Rpm
+    }
+
+    fn remove_command(&self, package: &str) -> Vec<String> {
+        vec![
+            "zypper".into(),
+            "--non-interactive".into(),
+            "remove".into(),
+            package.into(),
+        ]
+    }
+
+    fn dry_run_command(&self, package: &str) -> Vec<String> {
+        vec![
+            "zypper".into(),
+            "--non-interactive".into(),
+            "remove".into(),
+            "--dry-run".into(),
+            package.into(),
+        ]
+    }
+
+    fn dry_run_needs_root(&self) -> bool {
+        true
+    }
+
+    /* The packages are listed side by side under "The following packages are going to be REMOVED:". */
+    fn parse_dry_run(&self, output: &[String]) -> Vec<String> {
+        indented_under(output, |header| header.contains("going to be REMOVED"))
+            .flat_map(str::split_whitespace)
+            .map(str::to_owned)
+            .collect()
+    }
+}
+
+impl PackageBackend for Pacman {
+    fn name(&self) -> &str {
+        "pacman"
+    }
+
+    fn manages(&self) -> PackageManager {
+        PackageManager::Pacman
+    }
+
+    fn remove_command(&self, package: &str) -> Vec<String> {
+        vec![
+            "pacman".into(),
+            "-R".into(),
+            "--noconfirm".into(),
+            package.into(),
+        ]
+    }
+
+    fn dry_run_command(&self, package: &str) -> Vec<String> {
+        vec![
+            "pacman".into(),
+            "-Rp".into(),
+            "--print-format".into(),
+            "%n".into(),
+            package.into(),
+        ]
+    }
+
+    /* Only the names of the packages are printed, one per line. Errors and warnings have spaces in them. */
+    fn parse_dry_run(&self, output: &[String]) -> Vec<String> {
+        output
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.contains(char::is_whitespace))
+            .map(str::to_owned)
+            .collect()
+    }
+}
+
+/* The indented lines that follow a header line, up to the next line that isn't indented. */
+fn indented_under(
+    output: &[String],
+    is_header: impl Fn(&str) -> bool,
+) -> impl Iterator<Item = &str> {
+    let mut under_header = false;
+    output.iter().filter_map(move |line| {
+        if !line.starts_with(char::is_whitespace) {
+            under_header = is_header(line);
+            return None;
+        }
+        under_header.then_some(line.as_str())
+    })
+}
+
+/* Every backend we know of, in order of preference. */
+pub fn backends() -> Vec<Box<dyn PackageBackend + Send>> {
+    vec![
+        Box::new(Apt),
+        Box::new(Dnf),
+        Box::new(Zypper),
+        Box::new(Pacman),
+    ]
+}
+
+/* The first available backend that can remove a package owned by the given package manager. */
+pub fn backend_for(
+    owner: &PackageOwner,
+    backends: Vec<Box<dyn PackageBackend + Send>>,
+) -> Result<Box<dyn PackageBackend + Send>, Error> {
+    backends
+        .into_iter()
+        .find(|b| b.manages() == owner.manager && b.is_available())
+        .ok_or(Error::NoPackageBackend(owner.manager))
+}
+
+/* The full command that removes the package, through pkexec unless we are root already. */
+pub fn removal_command(backend: &dyn PackageBackend, package: &str) -> Vec<String> {
+    privileged(backend.remove_command(package))
+}
+
+/*
+ * Runs the dry run of removing the package and returns every package that would go, the package itself
+ * included. Fails if the dry run doesn't name any, as that means the package manager refuses to remove it
+ * (eg. because of a dependency) or it printed something we don't understand.
+ */
+pub fn dry_run(backend: &dyn PackageBackend, package: &str) -> Result<Vec<String>, Error> {
+    /* The output has to be parsed, so it can't be translated. */
+    let mut argv = vec!["env".to_string(), "LC_ALL=C".to_string()];
+    argv.extend(backend.dry_run_command(package));
+    if backend.dry_run_needs_root() {
+        argv = privileged(argv);
+    }
+
+    let mut output = Vec::new();
+    /* Some dry runs, like `dnf --assumeno`, end with a failure even when they went well. */
+    let result = run(&argv, |line| output.push(line.to_owned()));
+    let packages = backend.parse_dry_run(&output);
+    match result {
+        Ok(()) if packages.is_empty() => {
+            Err(Error::PackageManagerFailed(display_command(&argv), None))
+        }
+        Err(e) if packages.is_empty() => Err(e),
+        _ => Ok(packages),
+    }
+}
+
+/* Prepends pkexec to a command that needs root, unless we are root already. */
+pub fn privileged(mut argv: Vec<String>) -> Vec<String> {
+    if unsafe { libc::getuid() } != 0 {
+        argv.insert(0, "pkexec".into());
+    }
+    argv
+}
+
+/* A command as it would be typed in a shell, for showing it to the user. */
+pub fn display_command(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| {
+            if !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./=:+@".contains(c))
+            {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/*
+ * Runs a command, calling `on_line` with every line it prints (on either stdout or stderr) as soon as
+ * it is printed. Succeeds only if the command exits with status 0.
+ */
+pub fn run(argv: &[String], mut on_line: impl FnMut(&str)) -> Result<(), Error> {
+    let cmdline = display_command(argv);
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| Error::PackageManagerFailed(cmdline.clone(), None))?;
+    log::info!("Running {}", cmdline);
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::CouldNotRun(cmdline.clone(), e))?;
+
+    let (tx, rx) = mpsc::channel();
+    let forward = |stream: Option<Box<dyn Read + Send>>| {
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            if let Some(stream) = stream {
+                BufReader::new(stream)
+                    .lines()
+                    .map_while(Result::ok)
+                    .for_each(|line| tx.send(line).unwrap_or(()));
+            }
+        })
+    };
+    let readers = [
+        forward(
+            child
+                .stdout
+                .take()
+                .map(|s| Box::new(s) as Box<dyn Read + Send>),
+        ),
+        forward(
+            child
+                .stderr
+                .take()
+                .map(|s| Box::new(s) as Box<dyn Read + Send>),
+        ),
+    ];
+    drop(tx);
+
+    /* Ends once both streams are closed, that is when the command is done printing. */
+    rx.iter().for_each(|line| on_line(&line));
+    readers.into_iter().for_each(|r| r.join().unwrap_or(()));
+
+    let status = child
+        .wait()
+        .map_err(|e| Error::CouldNotRun(cmdline.clone(), e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::PackageManagerFailed(cmdline, status.code()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* A backend running shell scripts instead of a package manager. */
+    struct Fake {
+        manager: PackageManager,
+        program: &'static str,
+        dry_run: &'static str,
+    }
+
+    impl PackageBackend for Fake {
+        fn name(&self) -> &str {
+            self.program
+        }
+
+        fn manages(&self) -> PackageManager {
+            self.manager
+        }
+
+        fn remove_command(&self, package: &str) -> Vec<String> {
+            vec![
+                self.program.into(),
+                "-c".into(),
+                "exit 0".into(),
+                package.into(),
+            ]
+        }
+
+        fn dry_run_command(&self, package: &str) -> Vec<String> {
+            vec![
+                self.program.into(),
+                "-c".into(),
+                self.dry_run.into(),
+                "sh".into(),
+                package.into(),
+            ]
+        }
+
+        fn parse_dry_run(&self, output: &[String]) -> Vec<String> {
+            Apt.parse_dry_run(output)
+        }
+    }
+
+    fn fake(
+        manager: PackageManager,
+        program: &'static str,
+        dry_run: &'static str,
+    ) -> Box<dyn PackageBackend + Send> {
+        Box::new(Fake {
+            manager,
+            program,
+            dry_run,
+        })
+    }
+
+    fn owner(manager: PackageManager) -> PackageOwner {
+        PackageOwner {
+            manager,
+            package: "foo".into(),
+        }
+    }
+
+    #[test]
+    fn backends_are_picked_by_manager_and_availability() {
+        let backends = || {
+            vec![
+                fake(PackageManager::Pacman, "sh", ""),
+                fake(PackageManager::Dpkg, "appuninstaller-missing-program", ""),
+                fake(PackageManager::Dpkg, "sh", ""),
+            ]
+        };
+
+        let backend = backend_for(&owner(PackageManager::Dpkg), backends()).unwrap();
+        assert_eq!(backend.manages(), PackageManager::Dpkg);
+        assert_eq!(backend.name(), "sh");
+        assert!(matches!(
+            backend_for(&owner(PackageManager::Rpm), backends()),
+            Err(Error::NoPackageBackend(PackageManager::Rpm))
+        ));
+
+        let argv = removal_command(backend.as_ref(), "foo");
+        let escalated = unsafe { libc::getuid() } != 0;
+        assert_eq!(argv[0] == "pkexec", escalated);
+        assert_eq!(argv[escalated as usize..], backend.remove_command("foo"));
+    }
+
+    #[test]
+    fn dry_runs_list_the_removed_packages() {
+        let dry_run = |script| dry_run(fake(PackageManager::Dpkg, "sh", script).as_ref(), "foo");
+
+        assert_eq!(
+            dry_run("echo Remv $1 [1.0]; echo Remv libfoo [1.0]").unwrap(),
+            ["foo", "libfoo"]
+        );
+        /* The outcome counts, not the exit status. */
+        assert_eq!(dry_run("echo Remv $1 [1.0]; exit 1").unwrap(), ["foo"]);
+        assert!(dry_run("echo Nothing to do").is_err());
+        assert!(dry_run("echo $1 is needed by bar; exit 1").is_err());
+    }
+
+    #[test]
+    fn dry_run_outputs_are_parsed() {
+        let lines = |s: &str| s.lines().map(str::to_owned).collect::<Vec<_>>();
+
+        let apt =
+            "Reading package lists...\nThe following packages will be REMOVED:\n  foo libfoo\n\
+            Remv foo [1.0-1]\nRemv libfoo [1.0-1]\n";
+        assert_eq!(Apt.parse_dry_run(&lines(apt)), ["foo", "libfoo"]);
+
+        let dnf = "Dependencies resolved.\n=====\n Package  Arch  Version  Repository  Size\n=====\nRemoving:\n \
+            foo  x86_64  1.0-1  @fedora  1 M\nRemoving dependent packages:\n bar  x86_64  2.0-1  @fedora  2 M\n\n\
+            Transaction Summary\n=====\nRemove  2 Packages\n\nOperation aborted.\n";
+        assert_eq!(Dnf.parse_dry_run(&lines(dnf)), ["foo", "bar"]);
+
+        let zypper = "Resolving package dependencies...\n\nThe following 2 packages are going to be REMOVED:\n  \
+            bar foo\n\n2 packages to remove.\n";
+        assert_eq!(Zypper.parse_dry_run(&lines(zypper)), ["bar", "foo"]);
+
+        let pacman = "foo\nlibfoo\n";
+        assert_eq!(Pacman.parse_dry_run(&lines(pacman)), ["foo", "libfoo"]);
+        let pacman = "error: failed to prepare transaction (could not satisfy dependencies)\n\
+            :: removing foo breaks dependency 'foo' required by bar\n";
+        assert!(Pacman.parse_dry_run(&lines(pacman)).is_empty());
+    }
+}
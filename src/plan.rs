@@ -0,0 +1,405 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::desktop::{self, DesktopEntry};
+use crate::error::Error;
+use crate::exec::{self, ExecCommand};
+use crate::flatpak;
+use crate::history::RemovedItem;
+use crate::icons;
+use crate::installroot;
+use crate::pkgdb::PackageDb;
+use crate::purge::{AppPurgeProcess, AppPurger, Confidence, RemovalMethod};
+use crate::safety;
+use crate::snap;
+use crate::usage;
+use crate::utils;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/* How likely it is that removing an item breaks something other than the app itself. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Risk {
+    Low,
+    Medium,
+    High,
+}
+
+impl fmt::Display for Risk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemKind {
+    File,
+    Directory,
+}
+
+/* What an item is to the app. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemRole {
+    DesktopEntry,
+    Executable,
+    /* Icons and registrations made when an AppImage was integrated into the desktop. */
+    Integration,
+    /* The directory a manually installed app lives in, see installroot::find_root(). */
+    InstallRoot,
+    /* The files of the icon of the entry, in every theme and size. */
+    Icon,
+    Data,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlanItem {
+    pub path: PathBuf,
+    pub kind: ItemKind,
+    pub role: ItemRole,
+    /* In bytes, for directories this includes everything inside them. */
+    pub size: u64,
+    pub reason: String,
+    pub risk: Risk,
+    /* How sure we are that a leftover belongs to the app, for leftovers only. */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<Confidence>,
+    /* Only selected items are removed when the plan is executed. */
+    pub selected: bool,
+}
+
+/*
+ * Everything that would be removed to uninstall an app. Making a plan doesn't touch the filesystem,
+ * so it can be reviewed (and saved) first, and executing it removes exactly the selected items and nothing else.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UninstallPlan {
+    pub app: String,
+    #[serde(default)]
+    pub desktop_entry: PathBuf,
+    pub items: Vec<PlanItem>,
+}
+
+impl UninstallPlan {
+    /*
+     * Plans the removal of an app. Apps that belong to a package of the system package manager must be
+     * removed through it, so they are refused unless `allow_packaged` is set, and even then only what the
+     * package doesn't own can be selected. Flatpak and snap apps are always refused, as their only file
+     * outside their installation is the desktop entry.
+     */
+    pub fn new(entry: &DesktopEntry, allow_packaged: bool) -> Result<Self, Error> {
+        Self::with_package_db(entry, PackageDb::system(), allow_packaged)
+    }
+
+    /* Same as new(), but checks ownership against the given package databases instead of the system ones. */
+    pub fn with_package_db(
+        entry: &DesktopEntry,
+        db: &PackageDb,
+        allow_packaged: bool,
+    ) -> Result<Self, Error> {
+        if let Some(app) = flatpak::find_app(entry) {
+            return Err(Error::FlatpakApp(entry.name.clone(), app.name()));
+        }
+        if let Some(snap) = snap::find_snap(entry) {
+            return Err(Error::SnapApp(entry.name.clone(), snap.name));
+        }
+        if let Some(owner) = db.entry_owner(entry).filter(|_| !allow_packaged) {
+            return Err(Error::PackageManaged(entry.name.clone(), owner.clone()));
+        }
+
+        let home = utils::home_dir()?;
+        let mut plan = Self {
+            app: entry.name.clone(),
+            desktop_entry: PathBuf::from(&entry.full_path),
+            items: Vec::new(),
+        };
+        /* Anything outside the home directory is shared with the rest of the system, hence the higher risk. */
+        let risk = |path: &Path, in_home: Risk, elsewhere: Risk| {
+            if path.starts_with(&home) {
+                in_home
+            } else {
+                elsewhere
+            }
+        };
+
+        let entry_path = PathBuf::from(&entry.full_path);
+        let entry_risk = risk(&entry_path, Risk::Low, Risk::Medium);
+        plan.push(
+            entry_path,
+            ItemRole::DesktopEntry,
+            "Desktop entry of the application",
+            entry_risk,
+        );
+        /* Otherwise they'd take its place and the app would still show up. */
+        for shadowed in &entry.shadowed {
+            let shadowed = PathBuf::from(shadowed);
+            let shadowed_risk = risk(&shadowed, Risk::Low, Risk::Medium);
+            plan.push(
+                shadowed,
+                ItemRole::DesktopEntry,
+                "Desktop entry with the same ID, hidden by the one above",
+                shadowed_risk,
+            );
+        }
+
+        if let Some(image) = &entry.appimage {
+            let image_risk = risk(&image.path, Risk::Medium, Risk::High);
+            plan.push(
+                image.path.clone(),
+                ItemRole::Executable,
+                "AppImage launched by the desktop entry",
+                image_risk,
+            );
+
+            let icon = entry.icon_path.as_deref().unwrap_or_default();
+            for path in image.integration_files(icon, Path::new(&entry.full_path))? {
+                let integration_risk = risk(&path, Risk::Low, Risk::Medium);
+                plan.push(
+                    path,
+                    ItemRole::Integration,
+                    "Created when the AppImage was integrated into the desktop",
+                    integration_risk,
+                );
+            }
+        } else if let Some(exec) = AppPurger::find_exec(entry) {
+            let exec_risk = risk(&exec, Risk::Medium, Risk::High);
+            let reason = match ExecCommand::parse(&entry.exec)
+                .ok()
+                .and_then(|c| c.interpreter)
+            {
+                Some(interpreter) => {
+                    format!("Run by {} when the desktop entry is launched", interpreter)
+                }
+                None => "Executable launched by the desktop entry".to_string(),
+            };
+            let hops = exec::follow_launcher(&exec);
+            plan.push(exec.clone(), ItemRole::Executable, &reason, exec_risk);
+
+            /* Symlinks and wrapper scripts only point at the real program, which must go too */
+            let mut launcher = exec;
+            for hop in hops {
+                let hop_risk = risk(&hop, Risk::Medium, Risk::High);
+                let reason = format!("Real location of {}", launcher.display());
+                plan.push(hop.clone(), ItemRole::Executable, &reason, hop_risk);
+                launcher = hop;
+            }
+        }
+
+        let root = installroot::find_root(entry);
+        if let Some(root) = &root {
+            let root_risk = risk(root, Risk::Medium, Risk::High);
+            plan.push(
+                root.clone(),
+                ItemRole::InstallRoot,
+                "Directory the application is installed in, with everything inside it",
+                root_risk,
+            );
+        }
+
+        /* Icons other entries show stay, or they'd be left without one */
+        for path in icons::unshared_icon_files(entry, &desktop::load_entries()) {
+            let icon_risk = risk(&path, Risk::Low, Risk::Medium);
+            let reason = match icons::theme_of(&path).as_deref().and_then(Path::file_name) {
+                Some(theme) => format!(
+                    "Icon of the application in the {} theme",
+                    theme.to_string_lossy()
+                ),
+                None => "Icon of the application".to_string(),
+            };
+            plan.push(path, ItemRole::Icon, &reason, icon_risk);
+        }
+
+        for leftover in AppPurgeProcess::for_entry(entry).find_leftovers()? {
+            let data_risk = risk(&leftover.path, Risk::Medium, Risk::High);
+            let reason = format!("{} ({} confidence)", leftover.reason, leftover.confidence);
+            let selected = leftover.selected_by_default();
+            if let Some(item) = plan.push(leftover.path, ItemRole::Data, &reason, data_risk) {
+                item.confidence = Some(leftover.confidence);
+                item.selected = selected;
+            }
+        }
+
+        /* Whatever lies inside the install root goes along with it */
+        if let Some(root) = &root {
+            plan.items
+                .retain(|i| i.path == *root || !i.path.starts_with(root));
+        }
+
+        plan.protect(db);
+        Ok(plan)
+    }
+
+    /*
+     * Plans the removal of just the copy of a system entry the user made in their home directory, after
+     * which the system entry (and with it the app) shows up like it did before.
+     */
+    pub fn user_override(entry: &DesktopEntry) -> Result<Self, Error> {
+        if !entry.is_user_override() {
+            return Err(Error::NoUserOverride(entry.name.clone()));
+        }
+
+        let mut plan = Self {
+            app: entry.name.clone(),
+            desktop_entry: PathBuf::from(&entry.full_path),
+            items: Vec::new(),
+        };
+        plan.push(
+            PathBuf::from(&entry.full_path),
+            ItemRole::DesktopEntry,
+            &format!("User override of {}", entry.shadowed.join(", ")),
+            Risk::Low,
+        );
+        plan.protect(PackageDb::system());
+        Ok(plan)
+    }
+
+    /* Protected items stay in the plan so the user sees them, but removing them would be refused anyway. */
+    fn protect(&mut self, db: &PackageDb) {
+        for item in &mut self.items {
+            if let Err(Error::Protected(_, why)) = safety::check_with_db(&item.path, db) {
+                item.reason = format!("{} (protected, {})", item.reason, why);
+                item.risk = Risk::High;
+                item.selected = false;
+            }
+        }
+    }
+
+    /* Adds a path that currently exists to the plan, unless it is already part of it. */
+    fn push(
+        &mut self,
+        path: PathBuf,
+        role: ItemRole,
+        reason: &str,
+        risk: Risk,
+    ) -> Option<&mut PlanItem> {
+        let metadata = fs::symlink_metadata(&path).ok()?;
+        if self.items.iter().any(|i| i.path == path) {
+            return None;
+        }
+
+        self.items.push(PlanItem {
+            kind: if metadata.is_dir() {
+                ItemKind::Directory
+            } else {
+                ItemKind::File
+            },
+            size: usage::disk_usage(&path),
+            path,
+            role,
+            reason: reason.to_owned(),
+            risk,
+            confidence: None,
+            selected: true,
+        });
+        self.items.last_mut()
+    }
+
+    pub fn selected(&self) -> impl Iterator<Item = &PlanItem> {
+        self.items.iter().filter(|i| i.selected)
+    }
+
+    /* Bytes that executing the plan would free. */
+    pub fn total_size(&self) -> u64 {
+        self.selected().map(|i| i.size).sum()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("An uninstall plan can always be serialized")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|e| Error::InvalidPlan(e.to_string()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        fs::write(path, self.to_json()).map_err(|e| Error::Io(path.to_path_buf(), e))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let json = fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        Self::from_json(&json)
+    }
+
+    /*
+     * Removes every selected item, either by moving it to the trash or for good. An item that disappeared or turned from a file into a directory (or
+     * the other way around) since the plan was made is skipped, as it is no longer what was reviewed.
+     * One failure doesn't stop the rest of the plan, so every error is returned.
+     */
+    pub fn execute(&self, method: RemovalMethod) -> Execution {
+        log::info!("Executing the uninstall plan of \"{}\"", self.app);
+        let mut execution = Execution {
+            removed: Vec::new(),
+            errors: Vec::new(),
+        };
+        for item in self.selected() {
+            match item.remove(method) {
+                Ok(removed) => execution.removed.push(removed),
+                Err(e) => execution.errors.push(e),
+            }
+        }
+        icons::update_caches(
+            execution
+                .removed
+                .iter()
+                .filter_map(|item| icons::theme_of(&item.path)),
+        );
+        execution
+    }
+}
+
+/* The outcome of executing a plan. */
+#[derive(Debug)]
+pub struct Execution {
+    pub removed: Vec<RemovedItem>,
+    pub errors: Vec<Error>,
+}
+
+impl PlanItem {
+    fn remove(&self, method: RemovalMethod) -> Result<RemovedItem, Error> {
+        let metadata =
+            fs::symlink_metadata(&self.path).map_err(|_| Error::PlanMismatch(self.path.clone()))?;
+
+        let trashed = match (self.kind, metadata.is_dir()) {
+            (ItemKind::File, false) => AppPurger::remove_file(&self.path, method)?,
+            (ItemKind::Directory, true) => AppPurger::remove_data(&self.path, method)?,
+            _ => return Err(Error::PlanMismatch(self.path.clone())),
+        };
+
+        Ok(RemovedItem {
+            path: self.path.clone(),
+            kind: self.kind,
+            role: self.role,
+            size: self.size,
+            mode: metadata.mode(),
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            trashed,
+            restored: false,
+            restore_error: None,
+        })
+    }
+}
@@ -1,11 +1,23 @@
-use gtk::{prelude::*, Button, CheckButton, Label, Orientation, Window};
+use appuninstaller::purge::RemovalMethod;
+use appuninstaller::safety;
+use gtk::{prelude::*, Button, CheckButton, Label, Orientation, TextView, Window};
 use std::cell::RefCell;
+use std::path::PathBuf;
 use std::rc::Rc;
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct Preferences {
     pub startupdlg: bool,
     pub fullscreen: bool,
+    /* Delete files for good instead of moving them to the trash. */
+    pub permanent_delete: bool,
+    /* Allow uninstalling apps that belong to a package of the system package manager. */
+    pub allow_packaged: bool,
+    /* List entries that application menus leave out, like NoDisplay ones and settings panels of other desktops. */
+    pub show_hidden: bool,
+    /* Paths that are never removed, on top of the system directories and programs that never are. */
+    pub protected_paths: Vec<PathBuf>,
 }
 
 impl Preferences {
@@ -14,6 +26,15 @@ impl Preferences {
         confy::load::<Self>("LinuxAppUninstaller", None).unwrap()
     }
 
+    #[inline]
+    pub fn removal_method(&self) -> RemovalMethod {
+        if self.permanent_delete {
+            RemovalMethod::Permanent
+        } else {
+            RemovalMethod::Trash
+        }
+    }
+
     pub fn save(&self) {
         confy::store("LinuxAppUninstaller", None, self).unwrap_or_else(|e| {
             log::error!("Failed to save new settings on the disk: {}", e.to_string());
@@ -32,6 +53,16 @@ impl Preferences {
         let cnt = gtk::Box::new(Orientation::Vertical, 4);
         let startupdlg = CheckButton::with_label("Show warning on startup");
         let fullscreen = CheckButton::with_label("Start application in fullscreen");
+        let permanent_delete =
+            CheckButton::with_label("Delete files permanently instead of moving them to the trash");
+        let allow_packaged = CheckButton::with_label(
+            "Allow removing apps installed by the package manager (dangerous)",
+        );
+        let show_hidden = CheckButton::with_label("Show hidden entries");
+        let protected = TextView::builder()
+            .monospace(true)
+            .height_request(80)
+            .build();
         let savebtn = Button::with_label("Save changes");
 
         let prefs = Rc::new(RefCell::new(self.clone()));
@@ -52,10 +83,54 @@ impl Preferences {
             });
         }
 
+        permanent_delete.set_active(self.permanent_delete);
+        {
+            let prefs = Rc::clone(&prefs);
+            permanent_delete.connect_toggled(move |s| {
+                prefs.borrow_mut().permanent_delete = s.is_active();
+            });
+        }
+
+        allow_packaged.set_active(self.allow_packaged);
+        {
+            let prefs = Rc::clone(&prefs);
+            allow_packaged.connect_toggled(move |s| {
+                prefs.borrow_mut().allow_packaged = s.is_active();
+            });
+        }
+
+        show_hidden.set_active(self.show_hidden);
+        {
+            let prefs = Rc::clone(&prefs);
+            show_hidden.connect_toggled(move |s| {
+                prefs.borrow_mut().show_hidden = s.is_active();
+            });
+        }
+
+        let paths = self
+            .protected_paths
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>();
+        protected.buffer().set_text(&paths.join("\n"));
+        {
+            let prefs = Rc::clone(&prefs);
+            protected.buffer().connect_changed(move |b| {
+                let text = b.text(&b.start_iter(), &b.end_iter(), false);
+                prefs.borrow_mut().protected_paths = text
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(PathBuf::from)
+                    .collect();
+            });
+        }
+
         {
             let prefs = Rc::clone(&prefs);
             savebtn.connect_clicked(move |_| {
                 prefs.borrow().save();
+                safety::set_protected(prefs.borrow().protected_paths.clone());
                 log::info!("Saved new settings!");
             });
         }
@@ -67,6 +142,13 @@ impl Preferences {
 
         cnt.append(&startupdlg);
         cnt.append(&fullscreen);
+        cnt.append(&permanent_delete);
+        cnt.append(&allow_packaged);
+        cnt.append(&show_hidden);
+        cnt.append(&Label::new(Some(
+            "Never remove these paths or anything inside them (one per line):",
+        )));
+        cnt.append(&protected);
         cnt.append(&Label::new(Some(
             "You must restart the application to see the changes.",
         )));
@@ -82,6 +164,10 @@ impl Default for Preferences {
         Self {
             startupdlg: true,
             fullscreen: false,
+            permanent_delete: false,
+            allow_packaged: false,
+            show_hidden: false,
+            protected_paths: Vec::new(),
         }
     }
 }
@@ -1,6 +1,3 @@
-#![allow(deprecated)]
-
-use crate::dialog::Dialog;
 // This file is part of Linux Program Uninstaller.
 ///
 /// Linux Uninstaller - A fast, elegant program uninstaller for Linux
@@ -19,20 +16,19 @@ use crate::dialog::Dialog;
 ///  
 /// You should have received a copy of the GNU General Public License  
 /// along with this program.  If not, see <https://www.gnu.org/licenses/>.
-///
-use crate::utils::isolate_exec;
-use gtk::prelude::BoxExt;
-use gtk::prelude::DialogExt;
-use gtk::prelude::GtkWindowExt;
-use gtk::Dialog as GtkDialog;
-use gtk::Label;
-use gtk::ResponseType;
+use crate::desktop::DesktopEntry;
+use crate::error::Error;
+use crate::exec::ExecCommand;
+use crate::safety;
+use crate::trash::{self, TrashedItem};
+use crate::utils;
 use log::error;
 use log::info;
-use std::path::PathBuf;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
 
-/* Format: A variable of this array + the app's name in lowercase gives us a possible config dir which we test later on */
-const COMMON_DATA_DIRS: [&str; 9] = [
+/* Format: Directories in here named after one of the identifiers of the app are possible config dirs */
+pub(crate) const COMMON_DATA_DIRS: [&str; 9] = [
     "/usr/local/share/",
     "/usr/local/",
     "/usr/share/",
@@ -44,143 +40,696 @@ const COMMON_DATA_DIRS: [&str; 9] = [
     "/etc/",
 ];
 
-/* Format: $HOME + A variable of this array + the app's name in lowercase gives us a possible config dir which we test later on */
-const LOCAL_DATA_DIRS: [&str; 4] = [
-    "/.local/share/",
-    "/.", // Some apps save data under $HOME/.<app name>
-    "/.var",
-    "/.config",
+/*
+ * Format: Same as above, relative to $HOME. These are the defaults of the XDG base directories, which are
+ * searched even when $XDG_CONFIG_HOME and friends point elsewhere as not every app honors them. Directly
+ * in $HOME, only hidden ones count (eg. ~/.<app name>).
+ */
+const LOCAL_DATA_DIRS: [&str; 7] = [
+    ".local/share",
+    ".config",
+    ".cache",
+    ".local/state",
+    ".local/lib",
+    ".var/app", // Flatpak keeps the data of every app in ~/.var/app/<app id>
+    "",
+];
+
+/*
+ * Names of the standard directories leftovers are found in and of what is shared inside them. No app is
+ * identified by one of these, even if it has a name like "Fonts" or "Settings".
+ */
+const GENERIC_NAMES: [&str; 24] = [
+    "applications",
+    "autostart",
+    "backgrounds",
+    "bin",
+    "cache",
+    "config",
+    "data",
+    "desktop",
+    "doc",
+    "fonts",
+    "icons",
+    "include",
+    "lib",
+    "local",
+    "locale",
+    "man",
+    "mime",
+    "pixmaps",
+    "settings",
+    "share",
+    "sounds",
+    "state",
+    "systemd",
+    "themes",
 ];
 
+/* Where an identifier of an app comes from. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdentifierKind {
+    Name,
+    DesktopId,
+    /* The last part of a reverse-DNS desktop file ID, eg. "Nautilus" for org.gnome.Nautilus. */
+    ReverseDns,
+    WmClass,
+    Executable,
+    Icon,
+}
+
+impl std::fmt::Display for IdentifierKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Name => "name",
+            Self::DesktopId => "desktop file ID",
+            Self::ReverseDns => "desktop file ID part",
+            Self::WmClass => "StartupWMClass",
+            Self::Executable => "executable",
+            Self::Icon => "icon name",
+        })
+    }
+}
+
+impl IdentifierKind {
+    /* How much a match on this kind of identifier says on its own, out of 100. */
+    fn weight(self) -> i32 {
+        match self {
+            Self::DesktopId => 70,
+            Self::WmClass => 65,
+            Self::Name => 55,
+            Self::Executable => 50,
+            Self::ReverseDns => 45,
+            Self::Icon => 35,
+        }
+    }
+}
+
+/* Something an app goes by, which its leftovers can be named after. */
+#[derive(Clone, Debug)]
+pub struct Identifier {
+    pub kind: IdentifierKind,
+    pub value: String,
+}
+
+/* How files are removed. Trashing can be undone from any file manager, so it is what we do unless asked otherwise. */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemovalMethod {
+    #[default]
+    Trash,
+    Permanent,
+}
+
+/* How sure we are that a leftover belongs to the app. Only confident ones are removed unless asked for. */
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl std::fmt::Display for Confidence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        })
+    }
+}
+
+/* A path that looks like it belongs to an app, with how sure we are of that and why. */
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Leftover {
+    pub path: PathBuf,
+    /* From 0 to 100, see AppPurgeProcess::score(). */
+    pub score: u8,
+    pub confidence: Confidence,
+    /* Eg. "Matched StartupWMClass 'Code' in ~/.config" */
+    pub reason: String,
+}
+
+impl Leftover {
+    /* Low confidence leftovers are only shown, the user has to pick them to have them removed. */
+    pub fn selected_by_default(&self) -> bool {
+        self.confidence > Confidence::Low
+    }
+}
+
+/*
+ * When a file was created, in seconds since the Unix epoch. This is the birth time statx() reports, which
+ * not every filesystem keeps. The ctime is no substitute: it changes whenever the inode does (a chmod, a
+ * new hardlink, a rename...), so it says nothing about when the file appeared.
+ */
+fn birth_time(metadata: &std::fs::Metadata) -> Option<i64> {
+    let created = metadata.created().ok()?;
+    Some(
+        created
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs() as i64,
+    )
+}
+
 /* Does all the purging for us */
 pub struct AppPurger;
+
+/* Looks for data an app left behind. Nothing is removed here, the caller decides what to do with the results. */
 pub struct AppPurgeProcess {
-    app: String,
-    headless: bool,
+    /* Every identifier the app goes by, leftovers can be named after any of them. */
+    identifiers: Vec<Identifier>,
+    /* When the desktop entry was created, in seconds since the Unix epoch. See birth_time(). */
+    installed: Option<i64>,
 }
 
 impl AppPurger {
-    #[inline]
-    fn find_exec(exec: String) -> Option<PathBuf> {
-        match which::which(isolate_exec(exec.clone())) {
-            Ok(path) => Some(path),
+    /* The file the Exec key of an entry runs, see ExecCommand::resolve(). */
+    pub fn find_exec(entry: &DesktopEntry) -> Option<PathBuf> {
+        let working_dir = entry.working_dir.as_deref().map(Path::new);
+        match ExecCommand::parse(&entry.exec) {
+            Ok(command) => command.resolve(working_dir).or_else(|| {
+                error!("Failed to locate \"{}\"", command.program);
+                None
+            }),
             Err(e) => {
-                error!("Failed to locate \"{}\": {}", exec, e.to_string());
+                error!("{}", e);
                 None
             }
         }
     }
 
-    pub fn purge_app(appname: String, exec: PathBuf, entry: PathBuf) {
-        log::info!("Attempting to remove data of application \"{}\".", &appname);
-        let exec_path = exec.to_string_lossy();
-
-        if let Some(exec_file) = Self::find_exec(exec_path.to_string()) {
-            if let Err(e) = std::fs::remove_file(&exec_file) {
-                Dialog::new_without_parent("Error", &format!(
-                    "Failed to delete file '{}' from the filesystem:\n{}",
-                    exec_file.display(),
-                    e.to_string()
-                )).show();
+    /*
+     * Removes a single file, or the link itself if it is a symlink. When it is moved to the trash, where it
+     * ended up is returned so it can be restored later on. Protected paths (see safety::check()) are refused.
+     */
+    pub fn remove_file(path: &Path, method: RemovalMethod) -> Result<Option<TrashedItem>, Error> {
+        safety::check(path)?;
+        match method {
+            RemovalMethod::Trash => trash::trash(path).map(Some),
+            RemovalMethod::Permanent => {
+                std::fs::remove_file(path)
+                    .map_err(|e| Error::CouldNotDelete(path.to_path_buf(), e))?;
+                info!("Deleted file {}", path.display());
+                Ok(None)
             }
         }
+    }
 
-        if let Err(e) = std::fs::remove_file(&entry) {
-            Dialog::new_without_parent("Error", &format!(
-                "Failed to delete file '{}' from the filesystem:\n{}",
-                &entry.display(),
-                e.to_string()
-            )).show();
+    /* Removes a data directory found by AppPurgeProcess, see remove_file() for the return value. */
+    pub fn remove_data(path: &Path, method: RemovalMethod) -> Result<Option<TrashedItem>, Error> {
+        safety::check(path)?;
+        match method {
+            RemovalMethod::Trash => trash::trash(path).map(Some),
+            RemovalMethod::Permanent => {
+                std::fs::remove_dir_all(path)
+                    .map_err(|e| Error::CouldNotDelete(path.to_path_buf(), e))?;
+                info!("Deleted directory {}", path.display());
+                Ok(None)
+            }
         }
-
-        AppPurgeProcess::new(appname, false).try_purge();
     }
 }
 
 impl AppPurgeProcess {
-    pub fn new(app: String, headless: bool) -> Self {
-        Self { app, headless }
-    }
-
-    fn found_file_dialog(&self, path: PathBuf) {
-        if self.headless {
-            return;
-        }
-        log::info!("Found possible path at {}", path.display());
-        let dialog = GtkDialog::builder()
-            .title("Delete data")
-            .icon_name("question-symbolic")
-            .modal(true)
-            .margin_start(4)
-            .margin_end(4)
-            .margin_top(4)
-            .margin_bottom(4)
-            .build();
-
-        let content = dialog.content_area();
-        content.append(&Label::new(Some(&format!(
-            "Data for this application has been detected at {}. Delete it?",
-            path.display()
-        ))));
-        dialog.add_button("Yes, delete", ResponseType::Accept);
-        dialog.add_button("No, leave it", ResponseType::Cancel);
-        dialog.set_default_response(ResponseType::Cancel);
-
-        dialog.connect_response(move |d, response| match response {
-            ResponseType::Accept => {
-                let result = std::fs::remove_dir_all(path.clone());
-                if result.is_ok() {
-                    info!("Deleted directory {}", path.display());
-                } else {
-                    let e = result.unwrap_err();
-                    Dialog::new_without_parent(
-                        "Error",
-                        &format!("Couldn't remove data directory '{}': {}", path.display(), e),
-                    ).show();
+    pub fn new(app: String) -> Self {
+        let mut process = Self {
+            identifiers: Vec::new(),
+            installed: None,
+        };
+        process.add(IdentifierKind::Name, &app);
+        process
+    }
+
+    /*
+     * Looks for the leftovers of a desktop entry under everything that identifies it: its names in every
+     * language, its desktop file ID (and the app part of a reverse-DNS one), StartupWMClass, the file name
+     * of its executable and its icon name.
+     */
+    pub fn for_entry(entry: &DesktopEntry) -> Self {
+        let mut process = Self {
+            identifiers: Vec::new(),
+            installed: std::fs::symlink_metadata(&entry.full_path)
+                .ok()
+                .and_then(|m| birth_time(&m)),
+        };
+        for name in [&entry.name].into_iter().chain(&entry.names) {
+            process.add(IdentifierKind::Name, name);
+        }
+
+        let id = entry.id.strip_suffix(".desktop").unwrap_or(&entry.id);
+        process.add(IdentifierKind::DesktopId, id);
+        let parts = id.split('.').collect::<Vec<_>>();
+        if parts.len() >= 3 {
+            process.add(IdentifierKind::ReverseDns, parts[parts.len() - 1]);
+        }
+
+        if let Some(class) = &entry.wm_class {
+            process.add(IdentifierKind::WmClass, class);
+        }
+
+        if let Ok(command) = ExecCommand::parse(&entry.exec) {
+            let program = match (&command.interpreter, &command.payload) {
+                (Some(_), Some(payload)) => Some(payload.as_str()),
+                (Some(_), None) => None,
+                (None, _) => Some(command.program.as_str()),
+            };
+            if let Some(program) = program.map(Path::new) {
+                if let Some(name) = program.file_name() {
+                    process.add(IdentifierKind::Executable, &name.to_string_lossy());
+                }
+                if let Some(stem) = program.file_stem() {
+                    process.add(IdentifierKind::Executable, &stem.to_string_lossy());
                 }
-                d.close();
             }
-            _ => d.close(),
-        });
-        dialog.present();
+        }
+
+        /* Icon is either a name to look up in the icon theme or the path of the icon */
+        if let Some(icon) = entry.icon_path.as_deref().map(Path::new) {
+            if let Some(stem) = icon.file_stem() {
+                process.add(IdentifierKind::Icon, &stem.to_string_lossy());
+            }
+        }
+        process
+    }
+
+    /* Identifiers that can't be a single path component, or that only name a standard directory, are skipped. */
+    fn add(&mut self, kind: IdentifierKind, value: &str) {
+        let normalized = normalize(value);
+        let usable = !value.contains('/')
+            && normalized.len() >= 2
+            && !GENERIC_NAMES.contains(&normalized.as_str());
+        if usable
+            && !self
+                .identifiers
+                .iter()
+                .any(|i| i.kind == kind && normalize(&i.value) == normalized)
+        {
+            self.identifiers.push(Identifier {
+                kind,
+                value: value.to_owned(),
+            });
+        }
     }
 
-    pub fn find_app_files_global(&self) -> Vec<PathBuf> {
-        let mut paths = Vec::new();
+    pub fn find_app_files_global(&self) -> Vec<Leftover> {
+        let mut leftovers: Vec<Leftover> = Vec::new();
         for dir in COMMON_DATA_DIRS {
-            let path = PathBuf::from(dir).join(self.app.to_lowercase());
-            if path.exists() {
-                paths.push(path.clone());
-                self.found_file_dialog(path);
+            for leftover in self.matching_entries(Path::new(dir), false, None) {
+                if !leftovers.iter().any(|l| l.path == leftover.path) {
+                    leftovers.push(leftover);
+                }
+            }
+        }
+        leftovers
+    }
+
+    pub fn find_app_files_home(&self) -> Result<Vec<Leftover>, Error> {
+        let mut leftovers: Vec<Leftover> = Vec::new();
+        let homedir = utils::home_dir()?;
+
+        for dir in home_data_dirs(&homedir) {
+            for leftover in self.matching_entries(&dir, dir == homedir, Some(&homedir)) {
+                if !leftovers.iter().any(|l| l.path == leftover.path) {
+                    leftovers.push(leftover);
+                }
+            }
+        }
+        Ok(leftovers)
+    }
+
+    /*
+     * Entries of a directory named after one of the identifiers, ignoring case, a leading dot and any
+     * spaces, dashes, underscores and dots, so "Visual Studio Code" matches visual-studio-code too.
+     */
+    fn matching_entries(
+        &self,
+        dir: &Path,
+        hidden_only: bool,
+        home: Option<&Path>,
+    ) -> Vec<Leftover> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut leftovers = Vec::new();
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let Some(name) = file_name
+                .strip_prefix('.')
+                .or((!hidden_only).then_some(&file_name))
+            else {
+                continue;
+            };
+            let name = normalize(name);
+            let mut matched = self
+                .identifiers
+                .iter()
+                .filter(|i| normalize(&i.value) == name)
+                .collect::<Vec<_>>();
+            if !matched.is_empty() {
+                matched.sort_by_key(|i| -i.kind.weight());
+                let created = entry.metadata().ok().and_then(|m| birth_time(&m));
+                let leftover = self.score(entry.path(), &matched, home, created);
+                log::info!(
+                    "Found possible path at {} ({}, score {})",
+                    leftover.path.display(),
+                    leftover.reason,
+                    leftover.score
+                );
+                leftovers.push(leftover);
             }
         }
-        paths
+        leftovers.sort_by(|a, b| a.path.cmp(&b.path));
+        leftovers
     }
 
-    pub fn find_app_files_home(&self) -> Vec<PathBuf> {
-        let mut paths = Vec::new();
-        /* We can allow this function even though its deprecated because this app is not designed for Windows. */
-        #[allow(deprecated)]
-        let homedir = std::env::home_dir().unwrap_or_else(||{
-            log::error!("Couldn't find the home directory for the user. Crashing because no substitute can be used");
-            std::process::abort()
-        });
+    /*
+     * How likely a path matching the given identifiers (strongest first) belongs to the app. The kind of
+     * the strongest one sets the base score, which goes up when the identifier is long or others agree with
+     * it and when the path was created around the time the app was installed. It goes down for short
+     * identifiers, for paths in the home directory owned by someone else and for paths that were there
+     * well before the app. Creation times (see birth_time()) are only compared when the filesystem records
+     * them.
+     */
+    fn score(
+        &self,
+        path: PathBuf,
+        matched: &[&Identifier],
+        home: Option<&Path>,
+        created: Option<i64>,
+    ) -> Leftover {
+        const DAY: i64 = 24 * 60 * 60;
 
-        for dir in LOCAL_DATA_DIRS {
-            let path = homedir.join(dir).join(self.app.clone());
-            if path.exists() {
-                paths.push(path.clone());
-                self.found_file_dialog(path);
+        let best = matched[0];
+        let mut score = best.kind.weight();
+        let mut kinds = matched.iter().map(|i| i.kind).collect::<Vec<_>>();
+        kinds.dedup();
+        let identifiers = matched
+            .iter()
+            .take(2)
+            .map(|i| format!("{} '{}'", i.kind, i.value))
+            .collect::<Vec<_>>();
+        let dir = path.parent().unwrap_or(Path::new("/"));
+        let dir = match home.and_then(|h| dir.strip_prefix(h).ok()) {
+            Some(dir) if dir.as_os_str().is_empty() => "~".to_string(),
+            Some(dir) => format!("~/{}", dir.display()),
+            None => dir.display().to_string(),
+        };
+        let mut reason = format!("Matched {} in {}", identifiers.join(" and "), dir);
+
+        let length = normalize(&best.value).chars().count();
+        if length <= 3 {
+            score -= 20;
+            reason.push_str(", a name short enough to be a coincidence");
+        } else if length >= 10 {
+            score += 10;
+        }
+        if kinds.len() > 1 {
+            score += 10;
+        }
+
+        if let Ok(metadata) = std::fs::symlink_metadata(&path) {
+            let owner = home
+                .and_then(|h| std::fs::metadata(h).ok())
+                .map(|m| m.uid());
+            if owner.is_some_and(|uid| uid != metadata.uid()) {
+                score -= 40;
+                reason.push_str(", owned by another user");
             }
         }
-        paths
+
+        match self.installed.zip(created) {
+            Some((installed, created)) if (created - installed).abs() <= DAY => {
+                score += 15;
+                reason.push_str(", created when the app was installed");
+            }
+            Some((installed, created)) if created < installed - DAY => {
+                score -= 20;
+                reason.push_str(", older than the app");
+            }
+            _ => (),
+        }
+
+        let score = score.clamp(0, 100) as u8;
+        Leftover {
+            path,
+            score,
+            confidence: match score {
+                70.. => Confidence::High,
+                45.. => Confidence::Medium,
+                _ => Confidence::Low,
+            },
+            reason,
+        }
     }
 
-    #[inline]
-    pub fn try_purge(self) {
+    /* Every directory that looks like it belongs to the app, system-wide ones first. */
+    pub fn find_leftovers(&self) -> Result<Vec<Leftover>, Error> {
         log::info!("Trying global common paths");
-        self.find_app_files_global();
+        let mut paths = self.find_app_files_global();
         log::info!("Trying local common paths");
-        self.find_app_files_home();
+        paths.extend(self.find_app_files_home()?);
+        Ok(paths)
+    }
+}
+
+/* The XDG base directories of the user, followed by their defaults in case these are different. */
+fn home_data_dirs(home: &Path) -> Vec<PathBuf> {
+    let mut dirs = [
+        utils::data_home(),
+        utils::config_home(),
+        utils::cache_home(),
+        utils::state_home(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    for dir in LOCAL_DATA_DIRS.map(|d| home.join(d)) {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+/* An identifier or file name in the form they are compared in: lowercase, without separators. */
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_' | '.'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, id: &str, exec: &str) -> DesktopEntry {
+        DesktopEntry::new(
+            name.to_owned(),
+            exec.to_owned(),
+            None,
+            String::new(),
+            format!("/usr/share/applications/{}", id),
+        )
+    }
+
+    /* The names of the entries of a fixture directory that the app is matched with. */
+    fn matches(
+        process: &AppPurgeProcess,
+        dir: &Path,
+        names: &[&str],
+        hidden_only: bool,
+    ) -> Vec<String> {
+        for name in names {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+        }
+        process
+            .matching_entries(dir, hidden_only, None)
+            .iter()
+            .map(|l| l.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn reverse_dns_ids_are_matched() {
+        let process = AppPurgeProcess::for_entry(&entry(
+            "Files",
+            "org.gnome.Nautilus.desktop",
+            "nautilus --new-window %U",
+        ));
+        assert!(process
+            .identifiers
+            .iter()
+            .any(|i| i.kind == IdentifierKind::ReverseDns && i.value == "Nautilus"));
+
+        let dir = utils::test_dir("purge-reverse-dns");
+        let found = matches(
+            &process,
+            &dir,
+            &[
+                "org.gnome.Nautilus",
+                "Nautilus",
+                "gnome",
+                "org",
+                "org.gnome",
+            ],
+            false,
+        );
+        assert_eq!(found, ["Nautilus", "org.gnome.Nautilus"]);
+    }
+
+    #[test]
+    fn vendor_prefixed_names_are_matched_whole() {
+        let process = AppPurgeProcess::for_entry(&entry(
+            "Google Chrome",
+            "google-chrome.desktop",
+            "/usr/bin/google-chrome-stable %U",
+        ));
+        let dir = utils::test_dir("purge-vendor");
+        let found = matches(
+            &process,
+            &dir,
+            &[
+                "google-chrome",
+                "Google Chrome",
+                "google",
+                "chrome",
+                "google-chrome-beta",
+            ],
+            false,
+        );
+        assert_eq!(found, ["Google Chrome", "google-chrome"]);
+    }
+
+    #[test]
+    fn case_and_separators_are_ignored() {
+        for (name, normalized) in [
+            ("Visual Studio Code", "visualstudiocode"),
+            ("visual-studio-code", "visualstudiocode"),
+            ("Visual_Studio.Code", "visualstudiocode"),
+            ("ÉDITEUR", "éditeur"),
+        ] {
+            assert_eq!(normalize(name), normalized, "{}", name);
+        }
+
+        let process = AppPurgeProcess::new("Visual Studio Code".to_owned());
+        let dir = utils::test_dir("purge-normalize");
+        let found = matches(
+            &process,
+            &dir,
+            &[
+                "visual-studio-code",
+                "VisualStudioCode",
+                ".visual_studio_code",
+                "code",
+            ],
+            false,
+        );
+        assert_eq!(
+            found,
+            [
+                ".visual_studio_code",
+                "VisualStudioCode",
+                "visual-studio-code"
+            ]
+        );
+        /* Directly in $HOME only hidden directories count */
+        assert_eq!(matches(&process, &dir, &[], true), [".visual_studio_code"]);
+    }
+
+    #[test]
+    fn generic_names_are_not_identifiers() {
+        let process = AppPurgeProcess::for_entry(&entry(
+            "Settings",
+            "org.gnome.Settings.desktop",
+            "gnome-control-center",
+        ));
+        assert!(process
+            .identifiers
+            .iter()
+            .all(|i| normalize(&i.value) != "settings"));
+
+        let dir = utils::test_dir("purge-generic");
+        let found = matches(
+            &process,
+            &dir,
+            &[
+                "settings",
+                "Settings",
+                "config",
+                "org.gnome.Settings",
+                "gnome-control-center",
+            ],
+            false,
+        );
+        assert_eq!(found, ["gnome-control-center", "org.gnome.Settings"]);
+    }
+
+    const INSTALLED: i64 = 1_700_000_000;
+
+    fn scored(name: &str, created: Option<i64>) -> Leftover {
+        let process = AppPurgeProcess {
+            identifiers: Vec::new(),
+            installed: Some(INSTALLED),
+        };
+        let identifier = Identifier {
+            kind: IdentifierKind::Name,
+            value: name.to_owned(),
+        };
+        process.score(
+            PathBuf::from("/nonexistent").join(name),
+            &[&identifier],
+            None,
+            created,
+        )
+    }
+
+    #[test]
+    fn leftovers_created_with_the_app_score_high() {
+        let leftover = scored("Visual Studio Code", Some(INSTALLED + 60));
+        assert_eq!(leftover.score, 80);
+        assert_eq!(leftover.confidence, Confidence::High);
+        assert!(leftover.selected_by_default());
+        assert!(
+            leftover
+                .reason
+                .ends_with(", created when the app was installed"),
+            "{}",
+            leftover.reason
+        );
+    }
+
+    #[test]
+    fn short_and_older_matches_score_low() {
+        let leftover = scored("vlc", Some(INSTALLED - 30 * 24 * 60 * 60));
+        assert_eq!(leftover.score, 15);
+        assert_eq!(leftover.confidence, Confidence::Low);
+        assert!(!leftover.selected_by_default());
+        assert!(
+            leftover.reason.contains("short enough to be a coincidence"),
+            "{}",
+            leftover.reason
+        );
+        assert!(
+            leftover.reason.ends_with(", older than the app"),
+            "{}",
+            leftover.reason
+        );
+    }
+
+    #[test]
+    fn missing_birth_times_are_neutral() {
+        let leftover = scored("Visual Studio Code", None);
+        assert_eq!(leftover.score, 65);
+        assert_eq!(leftover.confidence, Confidence::Medium);
+        assert!(leftover.selected_by_default());
+        assert_eq!(
+            leftover.reason,
+            "Matched name 'Visual Studio Code' in /nonexistent"
+        );
     }
 }
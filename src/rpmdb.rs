@@ -0,0 +1,500 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/*
+ * Reads the file lists out of an rpm database (rpmdb.sqlite), without needing rpm or SQLite to be installed.
+ * Only what is needed for that is implemented: walking the table b-tree of the "Packages" table, and parsing
+ * the rpm headers stored as blobs in it. The older Berkeley DB and ndb formats are not supported.
+ */
+
+const RPMTAG_NAME: u32 = 1000;
+const RPMTAG_OLDFILENAMES: u32 = 1027;
+const RPMTAG_DIRINDEXES: u32 = 1116;
+const RPMTAG_BASENAMES: u32 = 1117;
+const RPMTAG_DIRNAMES: u32 = 1118;
+
+const RPM_INT32_TYPE: u32 = 4;
+const RPM_STRING_ARRAY_TYPE: u32 = 8;
+
+/* A package and every file it installed. */
+pub struct RpmPackage {
+    pub name: String,
+    pub files: Vec<PathBuf>,
+}
+
+/* Every package in the database, or None if the file isn't an SQLite rpm database we can read. */
+pub fn read_packages(db: &Path) -> Option<Vec<RpmPackage>> {
+    packages(&fs::read(db).ok()?)
+}
+
+fn packages(data: &[u8]) -> Option<Vec<RpmPackage>> {
+    let sqlite = Sqlite::new(data)?;
+    let root = sqlite.table_rows(1)?.iter().find_map(|row| {
+        let record = Record::parse(row);
+        (record.text(0) == Some("table") && record.text(1) == Some("Packages"))
+            .then(|| record.int(3))
+            .flatten()
+    })?;
+
+    Some(
+        sqlite
+            .table_rows(root as u32)?
+            .into_iter()
+            .filter_map(|row| Record::parse(&row).blob(1).and_then(parse_header))
+            .collect(),
+    )
+}
+
+/* Parses an rpm header as stored in the database, that is without the leading magic and reserved bytes. */
+fn parse_header(blob: &[u8]) -> Option<RpmPackage> {
+    let index_len = be32(blob, 0)? as usize;
+    let data_len = be32(blob, 4)? as usize;
+    let store_start = 8 + index_len * 16;
+    let store = blob.get(store_start..store_start + data_len)?;
+
+    let mut name = None;
+    let (mut basenames, mut dirnames, mut dirindexes, mut oldfilenames) = (None, None, None, None);
+    for i in 0..index_len {
+        let entry = 8 + i * 16;
+        let tag = be32(blob, entry)?;
+        let kind = be32(blob, entry + 4)?;
+        let offset = be32(blob, entry + 8)? as usize;
+        let count = be32(blob, entry + 12)? as usize;
+        let data = store.get(offset..)?;
+
+        match (tag, kind) {
+            (RPMTAG_NAME, _) => name = strings(data, 1)?.pop(),
+            (RPMTAG_BASENAMES, RPM_STRING_ARRAY_TYPE) => basenames = strings(data, count),
+            (RPMTAG_DIRNAMES, RPM_STRING_ARRAY_TYPE) => dirnames = strings(data, count),
+            (RPMTAG_OLDFILENAMES, RPM_STRING_ARRAY_TYPE) => oldfilenames = strings(data, count),
+            (RPMTAG_DIRINDEXES, RPM_INT32_TYPE) => {
+                dirindexes = (0..count)
+                    .map(|i| be32(data, i * 4))
+                    .collect::<Option<Vec<_>>>()
+            }
+            _ => {}
+        }
+    }
+
+    let files = match (basenames, dirnames, dirindexes) {
+        (Some(basenames), Some(dirnames), Some(dirindexes)) => basenames
+            .iter()
+            .zip(dirindexes)
+            .filter_map(|(base, dir)| {
+                Some(PathBuf::from(format!(
+                    "{}{}",
+                    dirnames.get(dir as usize)?,
+                    base
+                )))
+            })
+            .collect(),
+        _ => oldfilenames
+            .unwrap_or_default()
+            .into_iter()
+            .map(PathBuf::from)
+            .collect(),
+    };
+
+    Some(RpmPackage { name: name?, files })
+}
+
+/* Reads `count` NUL terminated strings. */
+fn strings(data: &[u8], count: usize) -> Option<Vec<String>> {
+    let strings = data
+        .split(|&b| b == 0)
+        .take(count)
+        .map(|s| String::from_utf8_lossy(s).into_owned())
+        .collect::<Vec<_>>();
+    (strings.len() == count).then_some(strings)
+}
+
+fn be32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn be16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+/* Reads an SQLite varint, returning it along with how many bytes it took. */
+fn varint(data: &[u8], at: usize) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for i in 0..9 {
+        let byte = *data.get(at + i)?;
+        if i == 8 {
+            return Some(((value << 8) | byte as u64, 9));
+        }
+        value = (value << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/* Just enough of the SQLite file format (https://www.sqlite.org/fileformat.html) to read table rows. */
+struct Sqlite<'a> {
+    data: &'a [u8],
+    page_size: usize,
+    usable_size: usize,
+}
+
+impl<'a> Sqlite<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        if !data.starts_with(b"SQLite format 3\0") {
+            return None;
+        }
+        let page_size = match be16(data, 16)? {
+            1 => 65536,
+            n => n as usize,
+        };
+        /* The limits the format puts on both sizes, anything else is a corrupted header. */
+        if !(512..=65536).contains(&page_size) || !page_size.is_power_of_two() {
+            return None;
+        }
+        let usable_size = page_size.checked_sub(*data.get(20)? as usize)?;
+        if usable_size < 480 {
+            return None;
+        }
+        Some(Self {
+            data,
+            page_size,
+            usable_size,
+        })
+    }
+
+    fn page(&self, number: u32) -> Option<&'a [u8]> {
+        let start = (number as usize).checked_sub(1)? * self.page_size;
+        self.data.get(start..start + self.page_size)
+    }
+
+    /* The payload of every row of the table whose b-tree starts at `root`. */
+    fn table_rows(&self, root: u32) -> Option<Vec<Vec<u8>>> {
+        let mut rows = Vec::new();
+        let mut pending = vec![root];
+        /* Guards against loops in corrupted files, a b-tree can't have more pages than the file. */
+        let mut budget = self.data.len() / self.page_size + 1;
+
+        while let Some(number) = pending.pop() {
+            budget = budget.checked_sub(1)?;
+            let page = self.page(number)?;
+            /* The first page starts with the 100 byte database header. */
+            let header = if number == 1 { 100 } else { 0 };
+            let kind = *page.get(header)?;
+            let cells = be16(page, header + 3)? as usize;
+            let pointers = header + if kind == 0x05 { 12 } else { 8 };
+
+            for i in 0..cells {
+                let cell = be16(page, pointers + i * 2)? as usize;
+                match kind {
+                    0x05 => pending.push(be32(page, cell)?),
+                    0x0d => rows.push(self.leaf_payload(page, cell)?),
+                    _ => return None,
+                }
+            }
+            if kind == 0x05 {
+                pending.push(be32(page, header + 8)?);
+            }
+        }
+        Some(rows)
+    }
+
+    /* Reads the payload of a table leaf cell, following overflow pages if it doesn't fit in the page. */
+    fn leaf_payload(&self, page: &[u8], cell: usize) -> Option<Vec<u8>> {
+        let (size, n) = varint(page, cell)?;
+        let (_, m) = varint(page, cell + n)?;
+        let start = cell + n + m;
+        let size = size as usize;
+        /* Also guards against loops of overflow pages in corrupted files. */
+        if size > self.data.len() {
+            return None;
+        }
+
+        let max_local = self.usable_size - 35;
+        if size <= max_local {
+            return page
+                .get(start..start.checked_add(size)?)
+                .map(<[u8]>::to_vec);
+        }
+
+        let min_local = (self.usable_size - 12) * 32 / 255 - 23;
+        let local = min_local + (size - min_local) % (self.usable_size - 4);
+        let local = if local <= max_local { local } else { min_local };
+
+        let mut payload = page.get(start..start + local)?.to_vec();
+        let mut next = be32(page, start + local)?;
+        while payload.len() < size && next != 0 {
+            let overflow = self.page(next)?;
+            let take = (size - payload.len()).min(self.usable_size - 4);
+            payload.extend_from_slice(overflow.get(4..4 + take)?);
+            next = be32(overflow, 0)?;
+        }
+        (payload.len() == size).then_some(payload)
+    }
+}
+
+/* A row of an SQLite table, decoded lazily column by column. */
+struct Record<'a> {
+    payload: &'a [u8],
+    /* Serial type and offset of each column. */
+    columns: Vec<(u64, usize)>,
+}
+
+impl<'a> Record<'a> {
+    fn parse(payload: &'a [u8]) -> Self {
+        let mut columns = Vec::new();
+        if let Some((header_len, n)) = varint(payload, 0) {
+            let (mut at, mut offset) = (n, header_len as usize);
+            while at < header_len as usize {
+                let Some((serial, n)) = varint(payload, at) else {
+                    break;
+                };
+                columns.push((serial, offset));
+                offset = offset.saturating_add(Self::len(serial));
+                at += n;
+            }
+        }
+        Self { payload, columns }
+    }
+
+    fn len(serial: u64) -> usize {
+        match serial {
+            0 | 8..=11 => 0,
+            1..=4 => serial as usize,
+            5 => 6,
+            6 | 7 => 8,
+            n => (n as usize - 12) / 2,
+        }
+    }
+
+    fn bytes(&self, column: usize) -> Option<(u64, &'a [u8])> {
+        let &(serial, offset) = self.columns.get(column)?;
+        Some((
+            serial,
+            self.payload
+                .get(offset..offset.checked_add(Self::len(serial))?)?,
+        ))
+    }
+
+    fn int(&self, column: usize) -> Option<i64> {
+        match self.bytes(column)? {
+            (8, _) => Some(0),
+            (9, _) => Some(1),
+            (1..=6, bytes) => {
+                /* Big endian two's complement, sign extended from the first byte. */
+                let init = if bytes[0] & 0x80 != 0 { -1i64 } else { 0 };
+                Some(bytes.iter().fold(init, |acc, &b| (acc << 8) | b as i64))
+            }
+            _ => None,
+        }
+    }
+
+    fn text(&self, column: usize) -> Option<&'a str> {
+        match self.bytes(column)? {
+            (serial, bytes) if serial >= 13 && serial % 2 == 1 => std::str::from_utf8(bytes).ok(),
+            _ => None,
+        }
+    }
+
+    fn blob(&self, column: usize) -> Option<&'a [u8]> {
+        match self.bytes(column)? {
+            (serial, bytes) if serial >= 12 && serial % 2 == 0 => Some(bytes),
+            _ => None,
+        }
+    }
+}
+
+/* Builds an rpmdb.sqlite holding the given packages and their files, for the tests. */
+#[cfg(test)]
+pub(crate) fn test_database(packages: &[(&str, &[&str])]) -> Vec<u8> {
+    const PAGE_SIZE: usize = 512;
+
+    fn varint(value: usize) -> Vec<u8> {
+        match value {
+            0..=0x7f => vec![value as u8],
+            _ => vec![0x80 | (value >> 7) as u8, (value & 0x7f) as u8],
+        }
+    }
+
+    /* Columns are given as their serial type and their bytes. */
+    fn cell(rowid: usize, columns: &[(usize, Vec<u8>)]) -> Vec<u8> {
+        let serials = columns
+            .iter()
+            .flat_map(|(serial, _)| varint(*serial))
+            .collect::<Vec<_>>();
+        let mut payload = varint(serials.len() + 1);
+        payload.extend(serials);
+        columns.iter().for_each(|(_, bytes)| payload.extend(bytes));
+
+        let mut cell = varint(payload.len());
+        cell.extend(varint(rowid));
+        cell.extend(payload);
+        cell
+    }
+
+    fn leaf_page(header: usize, cells: &[Vec<u8>]) -> Vec<u8> {
+        let mut page = vec![0; PAGE_SIZE];
+        page[header] = 0x0d;
+        page[header + 3..header + 5].copy_from_slice(&(cells.len() as u16).to_be_bytes());
+        let mut end = PAGE_SIZE;
+        for (i, cell) in cells.iter().enumerate() {
+            end -= cell.len();
+            page[end..end + cell.len()].copy_from_slice(cell);
+            let pointer = header + 8 + i * 2;
+            page[pointer..pointer + 2].copy_from_slice(&(end as u16).to_be_bytes());
+        }
+        page
+    }
+
+    fn text(s: &str) -> (usize, Vec<u8>) {
+        (s.len() * 2 + 13, s.as_bytes().to_vec())
+    }
+
+    fn header(name: &str, files: &[&str]) -> Vec<u8> {
+        let split = files
+            .iter()
+            .map(|f| {
+                f.rsplit_once('/')
+                    .map(|(dir, base)| (format!("{}/", dir), base))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let strings = |s: &mut dyn Iterator<Item = &str>| {
+            s.flat_map(|s| [s.as_bytes(), b"\0"].concat()).collect()
+        };
+        let dirnames = split
+            .iter()
+            .map(|(dir, _)| dir.as_str())
+            .collect::<Vec<_>>();
+        let tags: [(u32, u32, usize, Vec<u8>); 4] = [
+            (RPMTAG_NAME, 6, 1, strings(&mut [name].into_iter())),
+            (
+                RPMTAG_BASENAMES,
+                RPM_STRING_ARRAY_TYPE,
+                files.len(),
+                strings(&mut split.iter().map(|f| f.1)),
+            ),
+            (
+                RPMTAG_DIRNAMES,
+                RPM_STRING_ARRAY_TYPE,
+                files.len(),
+                strings(&mut dirnames.into_iter()),
+            ),
+            (
+                RPMTAG_DIRINDEXES,
+                RPM_INT32_TYPE,
+                files.len(),
+                (0..files.len() as u32).flat_map(u32::to_be_bytes).collect(),
+            ),
+        ];
+
+        let (mut index, mut store) = (Vec::new(), Vec::new());
+        for (tag, kind, count, data) in tags {
+            for value in [tag, kind, store.len() as u32, count as u32] {
+                index.extend(value.to_be_bytes());
+            }
+            store.extend(data);
+        }
+        let mut blob = ((index.len() / 16) as u32).to_be_bytes().to_vec();
+        blob.extend((store.len() as u32).to_be_bytes());
+        blob.extend(index);
+        blob.extend(store);
+        blob
+    }
+
+    let schema = cell(
+        1,
+        &[
+            text("table"),
+            text("Packages"),
+            text("Packages"),
+            (1, vec![2]),
+            text("CREATE TABLE Packages (hnum INTEGER PRIMARY KEY AUTOINCREMENT, blob BLOB NOT NULL)"),
+        ],
+    );
+    let rows = packages
+        .iter()
+        .enumerate()
+        .map(|(i, (name, files))| {
+            let blob = header(name, files);
+            cell(i + 1, &[(0, Vec::new()), (blob.len() * 2 + 12, blob)])
+        })
+        .collect::<Vec<_>>();
+
+    let mut data = leaf_page(100, &[schema]);
+    data[..16].copy_from_slice(b"SQLite format 3\0");
+    data[16..18].copy_from_slice(&(PAGE_SIZE as u16).to_be_bytes());
+    data.extend(leaf_page(0, &rows));
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packages_are_read() {
+        let data = test_database(&[
+            ("foo", &["/usr/bin/foo", "/usr/share/foo/data"]),
+            ("bar", &["/usr/bin/bar"]),
+        ]);
+        let packages = packages(&data).unwrap();
+
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "foo");
+        assert_eq!(
+            packages[0].files,
+            [
+                PathBuf::from("/usr/bin/foo"),
+                PathBuf::from("/usr/share/foo/data")
+            ]
+        );
+        assert_eq!(packages[1].name, "bar");
+    }
+
+    #[test]
+    fn corrupt_databases_are_rejected() {
+        let data = test_database(&[("foo", &["/usr/bin/foo"])]);
+        for len in 0..data.len() {
+            assert!(
+                packages(&data[..len]).is_none(),
+                "Truncated to {} bytes",
+                len
+            );
+        }
+
+        let corrupt = |at: usize, bytes: &[u8]| {
+            let mut data = data.clone();
+            data[at..at + bytes.len()].copy_from_slice(bytes);
+            packages(&data)
+        };
+        /* Page sizes that aren't a power of two between 512 and 65536, and too many reserved bytes. */
+        assert!(corrupt(16, &[0, 0]).is_none());
+        assert!(corrupt(16, &[0, 0xff]).is_none());
+        assert!(corrupt(16, &[0x03, 0xe8]).is_none());
+        assert!(corrupt(20, &[64]).is_none());
+        /* An interior page pointing to itself. */
+        assert!(corrupt(512, &[0x05, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]).is_none());
+        /* A row claiming to be far bigger than the file. */
+        let cell = be16(&data, 512 + 8).unwrap() as usize;
+        assert!(corrupt(512 + cell, &[0xff; 9]).is_none());
+    }
+}
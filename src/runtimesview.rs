@@ -0,0 +1,75 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use appuninstaller::flatpak::{self, RefKind};
+use appuninstaller::utils::format_size;
+use gtk::{prelude::*, Align, Label, Orientation, ScrolledWindow, Window};
+
+/* Lists the Flatpak runtimes that no installed app needs anymore, with how much space they take. */
+pub fn window() -> Window {
+    let window = Window::builder()
+        .title("Unused Flatpak runtimes")
+        .default_width(720)
+        .default_height(400)
+        .destroy_with_parent(true)
+        .icon_name("edit-clear-all")
+        .build();
+
+    let cnt = gtk::Box::new(Orientation::Vertical, 8);
+    cnt.set_margin_start(8);
+    cnt.set_margin_end(8);
+    cnt.set_margin_top(8);
+    cnt.set_margin_bottom(8);
+
+    let unused = flatpak::unused_runtimes(
+        &flatpak::installed(RefKind::App),
+        &flatpak::installed(RefKind::Runtime),
+    );
+    if unused.is_empty() {
+        cnt.append(&Label::new(Some(
+            "Every installed runtime is used by an app.",
+        )));
+    }
+
+    for runtime in &unused {
+        let label = Label::new(None);
+        label.set_markup(&format!(
+            "<b>{}</b> ({})\nFrom {}, {} installation, {}",
+            gtk::glib::markup_escape_text(&runtime.id),
+            gtk::glib::markup_escape_text(&runtime.branch),
+            gtk::glib::markup_escape_text(runtime.origin.as_deref().unwrap_or("an unknown remote")),
+            runtime.installation,
+            format_size(runtime.size)
+        ));
+        label.set_halign(Align::Start);
+        label.set_selectable(true);
+        cnt.append(&label);
+    }
+
+    if !unused.is_empty() {
+        let hint = Label::new(Some(&format!(
+            "{} in total. Run `flatpak uninstall --unused` to remove them.",
+            format_size(unused.iter().map(|r| r.size).sum())
+        )));
+        hint.set_halign(Align::Start);
+        cnt.append(&hint);
+    }
+
+    window.set_child(Some(&ScrolledWindow::builder().child(&cnt).build()));
+    window
+}
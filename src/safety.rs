@@ -0,0 +1,319 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::error::Error;
+use crate::exec::{self, SHELLS, WRAPPERS};
+use crate::pkgdb::PackageDb;
+use crate::purge::COMMON_DATA_DIRS;
+use crate::utils;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::RwLock;
+
+/*
+ * The last line of defense: every removal is checked here first, no matter how the path was found. A path
+ * that fails the check is refused outright, there is no way to force it.
+ */
+
+/* Directories whose loss would break the system or wipe the data of every app. */
+const CRITICAL_DIRS: [&str; 22] = [
+    "/",
+    "/bin",
+    "/boot",
+    "/dev",
+    "/efi",
+    "/home",
+    "/lib32",
+    "/lib64",
+    "/media",
+    "/mnt",
+    "/proc",
+    "/root",
+    "/run",
+    "/sbin",
+    "/srv",
+    "/sys",
+    "/tmp",
+    "/usr",
+    "/usr/bin",
+    "/usr/lib",
+    "/usr/sbin",
+    "/var",
+];
+
+/* Same, but relative to the home directory. */
+const CRITICAL_HOME_DIRS: [&str; 13] = [
+    "",
+    ".cache",
+    ".config",
+    ".local",
+    ".local/bin",
+    ".local/lib",
+    ".local/share",
+    ".local/share/applications",
+    ".local/state",
+    ".var",
+    ".var/app",
+    "snap",
+    "Applications",
+];
+
+/* Programs of coreutils and friends some desktop entries run, which no uninstall should take along. */
+const SYSTEM_PROGRAMS: [&str; 44] = [
+    "basename", "cat", "chgrp", "chmod", "chown", "cp", "cut", "date", "dd", "df", "dirname", "du",
+    "echo", "false", "head", "id", "install", "kill", "ln", "ls", "mkdir", "mktemp", "mount", "mv",
+    "printf", "pwd", "readlink", "realpath", "rm", "rmdir", "seq", "sleep", "sort", "stat", "su",
+    "tail", "tee", "test", "timeout", "touch", "true", "umount", "uname", "xdg-open",
+];
+
+/* Paths the user asked to never remove, from the preferences. */
+static USER_PROTECTED: RwLock<Vec<PathBuf>> = RwLock::new(Vec::new());
+
+/* Sets the paths the user protected, which are refused on top of the built in ones. */
+pub fn set_protected(paths: Vec<PathBuf>) {
+    *USER_PROTECTED.write().unwrap_or_else(|e| e.into_inner()) = paths;
+}
+
+/* Fails with Error::Protected if the path must never be removed. */
+pub fn check(path: &Path) -> Result<(), Error> {
+    check_with_db(path, PackageDb::system())
+}
+
+/* Same as check(), but with ownership checked against the given package databases. */
+pub fn check_with_db(path: &Path, db: &PackageDb) -> Result<(), Error> {
+    let home = utils::home_dir().ok();
+    check_against(path, db, &critical_dirs(home.as_deref()))
+}
+
+fn check_against(path: &Path, db: &PackageDb, critical: &[PathBuf]) -> Result<(), Error> {
+    let refuse = |reason: String| Err(Error::Protected(path.to_path_buf(), reason));
+    let path = &resolve_parent(path);
+
+    for dir in critical {
+        if dir.starts_with(path) {
+            return refuse(format!("it is or contains {}", dir.display()));
+        }
+    }
+
+    let user_protected = USER_PROTECTED
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    for protected in user_protected.iter().flat_map(|p| resolved(p)) {
+        if path.starts_with(&protected) || protected.starts_with(path) {
+            return refuse(format!(
+                "{} is protected in the preferences",
+                protected.display()
+            ));
+        }
+    }
+
+    /*
+     * A symlink to one of these programs in the home directory is fine to remove. Elsewhere it is most
+     * likely managed by the system, like the /usr/bin/python3 alternatives links.
+     */
+    let metadata = fs::symlink_metadata(path);
+    let is_dir = metadata.as_ref().is_ok_and(|m| m.is_dir());
+    let home_link = metadata.is_ok_and(|m| m.file_type().is_symlink())
+        && utils::home_dir().is_ok_and(|home| path.starts_with(home));
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    if !home_link && !is_dir {
+        if exec::is_interpreter(&name) || SHELLS.contains(&name.as_ref()) {
+            return refuse(format!("{} is an interpreter or shell", name));
+        }
+        if WRAPPERS.contains(&name.as_ref()) || SYSTEM_PROGRAMS.contains(&name.as_ref()) {
+            return refuse(format!("{} is a system program", name));
+        }
+    }
+
+    if let Some(owner) = db.owner(path) {
+        return refuse(format!("it belongs to the package {}", owner));
+    }
+    if let Some((file, owner)) = db.owned_under(path) {
+        return refuse(format!(
+            "it contains {}, which belongs to the package {}",
+            file.display(),
+            owner
+        ));
+    }
+    Ok(())
+}
+
+/*
+ * Every built in directory that is never removed, the roots of COMMON_DATA_DIRS included. They are
+ * resolved like the paths they are compared with, so a symlinked /home (eg. to /var/home on ostree
+ * systems) doesn't let ~/.config through as /var/home/<user>/.config.
+ */
+fn critical_dirs(home: Option<&Path>) -> Vec<PathBuf> {
+    let mut dirs = CRITICAL_DIRS
+        .iter()
+        .chain(COMMON_DATA_DIRS.iter())
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+    if let Some(home) = home {
+        dirs.extend(CRITICAL_HOME_DIRS.iter().map(|d| home.join(d)));
+    }
+    dirs.extend(
+        [
+            utils::data_home(),
+            utils::config_home(),
+            utils::cache_home(),
+            utils::state_home(),
+        ]
+        .into_iter()
+        .flatten(),
+    );
+    dirs.iter().flat_map(|d| resolved(d)).collect()
+}
+
+/*
+ * The forms a protected directory can show up in once resolve_parent() went over a path: as written, with
+ * its parent resolved, and fully resolved for when the directory itself is a symlink.
+ */
+fn resolved(dir: &Path) -> Vec<PathBuf> {
+    let mut forms = vec![normalize(dir)];
+    for form in [Some(resolve_parent(dir)), fs::canonicalize(dir).ok()]
+        .into_iter()
+        .flatten()
+    {
+        if !forms.contains(&form) {
+            forms.push(form);
+        }
+    }
+    forms
+}
+
+/*
+ * The path with its parent directory resolved, so /bin/bash is seen as /usr/bin/bash on a merged /usr
+ * and "..", "." and repeated slashes can't hide anything. The last component is left alone, as removing
+ * a symlink removes the link and not what it points to.
+ */
+fn resolve_parent(path: &Path) -> PathBuf {
+    let path = normalize(path);
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => fs::canonicalize(parent)
+            .unwrap_or_else(|_| parent.to_path_buf())
+            .join(name),
+        _ => path,
+    }
+}
+
+/* Lexically cleans up a path, without touching the filesystem. */
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => (),
+            c => normalized.push(c),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_db() -> PackageDb {
+        PackageDb::load(&utils::test_dir("safety-empty-root"))
+    }
+
+    #[test]
+    fn critical_dirs_are_refused() {
+        let db = empty_db();
+        for path in [
+            "/",
+            "/usr",
+            "/usr/",
+            "/usr/share",
+            "/usr/share/../bin",
+            "/var",
+            "/etc",
+        ] {
+            assert!(check_with_db(Path::new(path), &db).is_err(), "{}", path);
+        }
+        assert!(check_with_db(Path::new("/appuninstaller-test/app"), &db).is_ok());
+        assert!(check_with_db(Path::new("/appuninstaller-test/bin/bash"), &db).is_err());
+    }
+
+    #[test]
+    fn symlinked_homes_are_resolved() {
+        let dir = utils::test_dir("safety-symlinked-home");
+        fs::create_dir_all(dir.join("var/home/user/.config/app")).unwrap();
+        std::os::unix::fs::symlink(dir.join("var/home"), dir.join("home")).unwrap();
+        let home = dir.join("home/user");
+        let critical = critical_dirs(Some(&home));
+        let db = empty_db();
+
+        for path in [
+            home.join(".config"),
+            dir.join("var/home/user/.config"),
+            dir.join("var/home/user"),
+        ] {
+            assert!(
+                check_against(&path, &db, &critical).is_err(),
+                "{}",
+                path.display()
+            );
+        }
+        assert!(check_against(&home.join(".config/app"), &db, &critical).is_ok());
+        assert!(check_against(&dir.join("var/home/user/.config/app"), &db, &critical).is_ok());
+    }
+
+    #[test]
+    fn user_protected_dirs_are_refused() {
+        let dir = utils::test_dir("safety-user-protected");
+        fs::create_dir_all(dir.join("real/games/kept")).unwrap();
+        std::os::unix::fs::symlink(dir.join("real"), dir.join("link")).unwrap();
+        set_protected(vec![dir.join("link/games")]);
+        let db = empty_db();
+
+        assert!(check_with_db(&dir.join("real/games/kept"), &db).is_err());
+        assert!(check_with_db(&dir.join("link/games"), &db).is_err());
+        assert!(check_with_db(&dir.join("real"), &db).is_err());
+        assert!(check_with_db(&dir.join("real/other"), &db).is_ok());
+        set_protected(Vec::new());
+    }
+
+    #[test]
+    fn package_files_are_refused() {
+        let root = utils::test_dir("safety-packages");
+        fs::create_dir_all(root.join("var/lib/dpkg/info")).unwrap();
+        fs::write(
+            root.join("var/lib/dpkg/info/foo.list"),
+            "/appuninstaller-test/foo/lib/libfoo.so\n/appuninstaller-test/foo/bin/foo\n",
+        )
+        .unwrap();
+        let db = PackageDb::load(&root);
+
+        let check = |path: &str| check_with_db(Path::new(path), &db);
+        assert!(check("/appuninstaller-test/foo/bin/foo").is_err());
+        /* Not listed itself, but it holds files of the package. */
+        assert!(check("/appuninstaller-test/foo").is_err());
+        assert!(check("/appuninstaller-test/foo/bin").is_err());
+        assert!(check("/appuninstaller-test/foo/share").is_ok());
+        assert!(check("/appuninstaller-test/fo").is_ok());
+        assert!(check("/appuninstaller-test/foo-data").is_ok());
+    }
+}
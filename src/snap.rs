@@ -0,0 +1,212 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::desktop::DesktopEntry;
+use crate::error::Error;
+use crate::exec::ExecCommand;
+use crate::pkgmgr;
+use crate::usage;
+use crate::utils;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/* Where snapd puts the desktop entries of installed snaps, relative to the root directory. */
+const DESKTOP_DIR: &str = "var/lib/snapd/desktop/applications";
+
+/* An installed snap, as described by the meta/snap.yaml of its current revision. */
+#[derive(Clone, Debug, Serialize)]
+pub struct Snap {
+    pub name: String,
+    pub version: Option<String>,
+    pub summary: Option<String>,
+    /* The revision "current" points to, eg. "2345" or "x1" for snaps installed from a file. */
+    pub revision: String,
+    /* The mounted snap, that is <root>/snap/<name>/<revision> */
+    pub path: PathBuf,
+    /* In bytes, of the .snap file the revision is mounted from (or of the mounted files if it's missing). */
+    pub size: u64,
+    /* The apps the snap declares, launched as /snap/bin/<name>.<app> (or just <name> for the main one). */
+    pub apps: Vec<String>,
+}
+
+impl Snap {
+    /*
+     * Reads an installed snap from a root directory, "/" for the running system. Everything is looked up
+     * relative to it, so a fake /snap tree works just as well.
+     */
+    pub fn load(root: &Path, name: &str) -> Option<Self> {
+        let current = root.join("snap").join(name).join("current");
+        let revision = fs::read_link(&current)
+            .ok()?
+            .file_name()?
+            .to_string_lossy()
+            .into_owned();
+        let path = root.join("snap").join(name).join(&revision);
+        let yaml = fs::read_to_string(path.join("meta/snap.yaml")).ok()?;
+        let size = fs::metadata(
+            root.join("var/lib/snapd/snaps")
+                .join(format!("{}_{}.snap", name, revision)),
+        )
+        .map_or_else(|_| usage::disk_usage(&path), |m| m.len());
+
+        Some(Self {
+            name: yaml_value(&yaml, "name").unwrap_or(name).to_owned(),
+            version: yaml_value(&yaml, "version").map(ToOwned::to_owned),
+            summary: yaml_value(&yaml, "summary").map(ToOwned::to_owned),
+            apps: yaml_keys(&yaml, "apps")
+                .into_iter()
+                .map(ToOwned::to_owned)
+                .collect(),
+            revision,
+            path,
+            size,
+        })
+    }
+
+    /* Where the snap keeps the data of the current user, ~/snap/<name> */
+    pub fn data_dir(&self) -> Result<PathBuf, Error> {
+        Ok(utils::home_dir()?.join("snap").join(&self.name))
+    }
+
+    /* The command that removes the snap, through pkexec as snapd wants root for it. */
+    pub fn remove_command(&self) -> Vec<String> {
+        pkgmgr::privileged(vec!["snap".into(), "remove".into(), self.name.clone()])
+    }
+}
+
+/* Directory snapd exports the desktop entries of installed snaps to. */
+pub fn desktop_dir(root: &Path) -> PathBuf {
+    root.join(DESKTOP_DIR)
+}
+
+/*
+ * The name of the snap a desktop entry belongs to. snapd names the entries it exports
+ * "<snap>_<app>.desktop", and their command runs /snap/bin/<snap>[.<app>].
+ */
+pub fn snap_name(root: &Path, entry: &DesktopEntry) -> Option<String> {
+    let path = Path::new(&entry.full_path);
+    if path.starts_with(desktop_dir(root)) {
+        let stem = path.file_stem()?.to_string_lossy();
+        return stem.split('_').next().map(ToOwned::to_owned);
+    }
+
+    let command = ExecCommand::parse(&entry.exec).ok()?;
+    let app = Path::new(&command.program)
+        .strip_prefix(root.join("snap/bin"))
+        .ok()?;
+    app.to_str()?.split('.').next().map(ToOwned::to_owned)
+}
+
+/* The installed snap a desktop entry of the running system belongs to. */
+pub fn find_snap(entry: &DesktopEntry) -> Option<Snap> {
+    let root = Path::new("/");
+    Snap::load(root, &snap_name(root, entry)?)
+}
+
+/* A top level value of a snap.yaml, unquoted. Only the simple "key: value" form snapd itself writes is read. */
+fn yaml_value<'a>(yaml: &'a str, key: &str) -> Option<&'a str> {
+    yaml.lines().find_map(|l| {
+        let (k, v) = l.split_once(':')?;
+        let v = v.trim().trim_matches(|c| c == '"' || c == '\'');
+        (k == key && !v.is_empty()).then_some(v)
+    })
+}
+
+/* The keys of a top level mapping of a snap.yaml, eg. the names of the apps under "apps:". */
+fn yaml_keys<'a>(yaml: &'a str, key: &str) -> Vec<&'a str> {
+    let header = format!("{}:", key);
+    let block = yaml
+        .lines()
+        .skip_while(|l| l.trim_end() != header)
+        .skip(1)
+        .take_while(|l| l.trim().is_empty() || l.starts_with(' '))
+        .filter(|l| !l.trim().is_empty() && !l.trim().starts_with('#'))
+        .collect::<Vec<_>>();
+    let indent = |l: &str| l.len() - l.trim_start().len();
+    let first = block.first().map_or(0, |l| indent(l));
+
+    block
+        .into_iter()
+        .filter(|l| indent(l) == first)
+        .filter_map(|l| Some(l.trim().split_once(':')?.0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNAP_YAML: &str = "name: foo
+version: '1.2'
+summary: \"A foo editor\"
+apps:
+  foo:
+    command: bin/foo
+    plugs:
+      - home
+  # The daemon is an app as well
+  foo-daemon:
+    command: bin/foo-daemon
+    daemon: simple
+architectures:
+- amd64
+";
+
+    #[test]
+    fn snaps_are_read_from_their_current_revision() {
+        let root = utils::test_dir("snap-load");
+        fs::create_dir_all(root.join("snap/foo/x1/meta")).unwrap();
+        fs::create_dir_all(root.join("snap/foo/x2/meta")).unwrap();
+        fs::write(
+            root.join("snap/foo/x1/meta/snap.yaml"),
+            "name: foo\nversion: 1.0\n",
+        )
+        .unwrap();
+        fs::write(root.join("snap/foo/x2/meta/snap.yaml"), SNAP_YAML).unwrap();
+        std::os::unix::fs::symlink("x2", root.join("snap/foo/current")).unwrap();
+        fs::create_dir_all(root.join("var/lib/snapd/snaps")).unwrap();
+        fs::write(root.join("var/lib/snapd/snaps/foo_x2.snap"), [0; 4096]).unwrap();
+
+        let snap = Snap::load(&root, "foo").unwrap();
+        assert_eq!(snap.name, "foo");
+        assert_eq!(snap.version.as_deref(), Some("1.2"));
+        assert_eq!(snap.summary.as_deref(), Some("A foo editor"));
+        assert_eq!(snap.revision, "x2");
+        assert_eq!(snap.path, root.join("snap/foo/x2"));
+        assert_eq!(snap.size, 4096);
+        assert_eq!(snap.apps, ["foo", "foo-daemon"]);
+
+        /* Without "current" the snap isn't installed, whatever revisions are left over. */
+        fs::create_dir_all(root.join("snap/bar/1/meta")).unwrap();
+        assert!(Snap::load(&root, "bar").is_none());
+        assert!(Snap::load(&root, "baz").is_none());
+    }
+
+    #[test]
+    fn yaml_keys_stop_at_the_end_of_the_mapping() {
+        assert_eq!(yaml_keys(SNAP_YAML, "apps"), ["foo", "foo-daemon"]);
+        assert_eq!(yaml_keys(SNAP_YAML, "plugs"), Vec::<&str>::new());
+        assert_eq!(
+            yaml_keys("apps:\n\n    foo:\n        command: foo\n", "apps"),
+            ["foo"]
+        );
+        assert_eq!(yaml_value(SNAP_YAML, "command"), None);
+        assert_eq!(yaml_value(SNAP_YAML, "apps"), None);
+    }
+}
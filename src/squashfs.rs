@@ -0,0 +1,581 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use std::fs::File;
+use std::io::Read;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+/*
+ * Reads single files out of a squashfs image (https://dr-emann.github.io/squashfs/), like the one embedded
+ * in AppImages, without mounting it. Only what is needed for that is implemented: looking paths up through
+ * directories and symlinks, and reading regular files, gzip or zstd compressed.
+ */
+
+const MAGIC: &[u8] = b"hsqs";
+const GZIP: u16 = 1;
+const ZSTD: u16 = 6;
+
+/* Metadata (inodes, directories...) is stored in blocks of up to 8 KiB. */
+const METADATA_SIZE: usize = 8192;
+/* Set in the size of a block stored uncompressed. */
+const UNCOMPRESSED: u32 = 1 << 24;
+const NO_FRAGMENT: u32 = 0xffff_ffff;
+const MAX_LINKS: usize = 8;
+/* Nothing worth reading out of an image comes close, and it keeps corrupted sizes from running wild. */
+const MAX_FILE_SIZE: u64 = 1 << 20;
+
+pub struct Squashfs {
+    file: File,
+    /* Where the image starts in the file. */
+    offset: u64,
+    block_size: u32,
+    compressor: u16,
+    root: u64,
+    inode_table: u64,
+    directory_table: u64,
+    fragment_table: u64,
+}
+
+enum Inode {
+    Dir {
+        block: u32,
+        offset: u16,
+        size: u32,
+    },
+    File {
+        blocks_start: u64,
+        size: u64,
+        fragment: u32,
+        fragment_offset: u32,
+        blocks: Vec<u32>,
+    },
+    Symlink(String),
+}
+
+impl Squashfs {
+    /* Opens the image starting at `offset` in a file, or None if there is no squashfs 4.0 image there. */
+    pub fn open(path: &Path, offset: u64) -> Option<Self> {
+        let file = File::open(path).ok()?;
+        let mut superblock = [0; 96];
+        file.read_exact_at(&mut superblock, offset).ok()?;
+        if !superblock.starts_with(MAGIC) || le16(&superblock, 28)? != 4 {
+            return None;
+        }
+
+        let block_size = le32(&superblock, 12)?;
+        if !(4096..=1 << 20).contains(&block_size) || !block_size.is_power_of_two() {
+            return None;
+        }
+        Some(Self {
+            file,
+            offset,
+            block_size,
+            compressor: le16(&superblock, 20)?,
+            root: le64(&superblock, 32)?,
+            inode_table: le64(&superblock, 64)?,
+            directory_table: le64(&superblock, 72)?,
+            fragment_table: le64(&superblock, 80)?,
+        })
+    }
+
+    /* The names in a directory of the image, eg. "" for the root. */
+    pub fn list(&self, path: &str) -> Option<Vec<String>> {
+        let dir = self.lookup(path)?;
+        Some(
+            self.entries(&dir)?
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect(),
+        )
+    }
+
+    /* The contents of a regular file of the image, following symlinks. */
+    pub fn read(&self, path: &str) -> Option<Vec<u8>> {
+        match self.lookup(path)? {
+            Inode::File {
+                blocks_start,
+                size,
+                fragment,
+                fragment_offset,
+                blocks,
+            } => self.file_data(blocks_start, size, fragment, fragment_offset, &blocks),
+            _ => None,
+        }
+    }
+
+    fn lookup(&self, path: &str) -> Option<Inode> {
+        let mut pending = path.split('/').rev().map(str::to_owned).collect::<Vec<_>>();
+        let mut parents = Vec::new();
+        let mut current = self.root;
+        let mut links = 0;
+
+        while let Some(name) = pending.pop() {
+            match name.as_str() {
+                "" | "." => continue,
+                ".." => {
+                    current = parents.pop().unwrap_or(self.root);
+                    continue;
+                }
+                _ => {}
+            }
+            let (_, reference) = self
+                .entries(&self.inode(current)?)?
+                .into_iter()
+                .find(|(n, _)| *n == name)?;
+            match self.inode(reference)? {
+                Inode::Symlink(target) => {
+                    links += 1;
+                    if links > MAX_LINKS {
+                        return None;
+                    }
+                    if target.starts_with('/') {
+                        parents.clear();
+                        current = self.root;
+                    }
+                    pending.extend(target.split('/').rev().map(str::to_owned));
+                }
+                _ => {
+                    parents.push(current);
+                    current = reference;
+                }
+            }
+        }
+        self.inode(current)
+    }
+
+    /* Reads the inode a reference (the offset of its metadata block, shifted left by 16, plus its offset in it) points to. */
+    fn inode(&self, reference: u64) -> Option<Inode> {
+        let mut metadata = Metadata::new(self, self.inode_table, reference);
+        let header = metadata.read(16)?;
+        match le16(&header, 0)? {
+            1 => {
+                let dir = metadata.read(16)?;
+                Some(Inode::Dir {
+                    block: le32(&dir, 0)?,
+                    size: le16(&dir, 8)? as u32,
+                    offset: le16(&dir, 10)?,
+                })
+            }
+            8 => {
+                let dir = metadata.read(24)?;
+                Some(Inode::Dir {
+                    size: le32(&dir, 4)?,
+                    block: le32(&dir, 8)?,
+                    offset: le16(&dir, 18)?,
+                })
+            }
+            kind @ (2 | 9) => {
+                let (blocks_start, size, fragment, fragment_offset) = if kind == 2 {
+                    let file = metadata.read(16)?;
+                    let size = le32(&file, 12)? as u64;
+                    (
+                        le32(&file, 0)? as u64,
+                        size,
+                        le32(&file, 4)?,
+                        le32(&file, 8)?,
+                    )
+                } else {
+                    let file = metadata.read(40)?;
+                    (
+                        le64(&file, 0)?,
+                        le64(&file, 8)?,
+                        le32(&file, 28)?,
+                        le32(&file, 32)?,
+                    )
+                };
+                if size > MAX_FILE_SIZE {
+                    return None;
+                }
+                /* The tail of the file is in a fragment, shared with the tails of other files, if it has one. */
+                let block_size = self.block_size as u64;
+                let count = match fragment {
+                    NO_FRAGMENT => size.div_ceil(block_size),
+                    _ => size / block_size,
+                } as usize;
+                let blocks = metadata.read(count * 4)?;
+                Some(Inode::File {
+                    blocks_start,
+                    size,
+                    fragment,
+                    fragment_offset,
+                    blocks: (0..count)
+                        .map(|i| le32(&blocks, i * 4))
+                        .collect::<Option<_>>()?,
+                })
+            }
+            3 | 10 => {
+                let link = metadata.read(8)?;
+                let target = metadata.read((le32(&link, 4)? as usize).min(METADATA_SIZE))?;
+                Some(Inode::Symlink(String::from_utf8(target).ok()?))
+            }
+            _ => None,
+        }
+    }
+
+    /*
+     * The names in a directory along with references to their inodes. The listing is a series of headers,
+     * each followed by entries whose inodes are in the same metadata block.
+     */
+    fn entries(&self, dir: &Inode) -> Option<Vec<(String, u64)>> {
+        let Inode::Dir {
+            block,
+            offset,
+            size,
+        } = *dir
+        else {
+            return None;
+        };
+        /* The size counts 3 bytes that aren't there, for the "." and ".." entries. */
+        let size = (size as usize).checked_sub(3)?;
+        let mut metadata = Metadata::new(
+            self,
+            self.directory_table,
+            (block as u64) << 16 | offset as u64,
+        );
+        let listing = metadata.read(size)?;
+
+        let mut entries = Vec::new();
+        let mut at = 0;
+        while at < listing.len() {
+            let count = le32(&listing, at)? as usize + 1;
+            let start = le32(&listing, at + 4)? as u64;
+            at += 12;
+            for _ in 0..count {
+                let offset = le16(&listing, at)? as u64;
+                let name_size = le16(&listing, at + 6)? as usize + 1;
+                let name = listing.get(at + 8..at + 8 + name_size)?;
+                entries.push((
+                    String::from_utf8_lossy(name).into_owned(),
+                    start << 16 | offset,
+                ));
+                at += 8 + name_size;
+            }
+        }
+        Some(entries)
+    }
+
+    fn file_data(
+        &self,
+        mut at: u64,
+        size: u64,
+        fragment: u32,
+        fragment_offset: u32,
+        blocks: &[u32],
+    ) -> Option<Vec<u8>> {
+        let size = size as usize;
+        let mut data = Vec::with_capacity(size);
+        for &block in blocks {
+            match self.block(at, block)? {
+                /* A hole in a sparse file. */
+                sparse if sparse.is_empty() => {
+                    data.resize(data.len() + self.block_size as usize, 0)
+                }
+                block => data.extend(block),
+            }
+            at += (block & !UNCOMPRESSED) as u64;
+        }
+
+        if fragment != NO_FRAGMENT {
+            /* The fragment table is a list of pointers to metadata blocks of 16 byte entries. */
+            let index = fragment as u64 / (METADATA_SIZE as u64 / 16);
+            let pointer = le64(&self.read_at(self.fragment_table + index * 8, 8)?, 0)?;
+            let entry = Metadata::new(self, pointer, (fragment as u64 % 512) * 16).read(16)?;
+            let block = self.block(le64(&entry, 0)?, le32(&entry, 8)?)?;
+            let start = fragment_offset as usize;
+            data.extend(block.get(start..start + size.checked_sub(data.len())?)?);
+        }
+        data.truncate(size);
+        (data.len() == size).then_some(data)
+    }
+
+    /* Reads a data block given its position and its size as stored in the image, uncompressing it. */
+    fn block(&self, at: u64, size: u32) -> Option<Vec<u8>> {
+        let raw = self.read_at(at, (size & !UNCOMPRESSED) as usize)?;
+        if size & UNCOMPRESSED != 0 || raw.is_empty() {
+            return Some(raw);
+        }
+        self.decompress(&raw, self.block_size as usize)
+    }
+
+    fn decompress(&self, data: &[u8], max: usize) -> Option<Vec<u8>> {
+        let mut out = Vec::new();
+        let limit = max as u64;
+        match self.compressor {
+            GZIP => flate2::read::ZlibDecoder::new(data)
+                .take(limit)
+                .read_to_end(&mut out)
+                .ok()?,
+            ZSTD => ruzstd::decoding::StreamingDecoder::new(data)
+                .ok()?
+                .take(limit)
+                .read_to_end(&mut out)
+                .ok()?,
+            compressor => {
+                log::debug!("Unsupported squashfs compressor {}", compressor);
+                return None;
+            }
+        };
+        Some(out)
+    }
+
+    /* Reads bytes at a position relative to the start of the image. */
+    fn read_at(&self, at: u64, len: usize) -> Option<Vec<u8>> {
+        let mut bytes = vec![0; len];
+        self.file
+            .read_exact_at(&mut bytes, self.offset.checked_add(at)?)
+            .ok()?;
+        Some(bytes)
+    }
+}
+
+/* Reads a table of metadata blocks as one stream, starting from a reference into it. */
+struct Metadata<'a> {
+    image: &'a Squashfs,
+    /* Position of the next block to read. */
+    next: u64,
+    data: Vec<u8>,
+    at: usize,
+}
+
+impl<'a> Metadata<'a> {
+    fn new(image: &'a Squashfs, table: u64, reference: u64) -> Self {
+        Self {
+            image,
+            next: table.saturating_add(reference >> 16),
+            data: Vec::new(),
+            at: (reference & 0xffff) as usize,
+        }
+    }
+
+    fn read(&mut self, len: usize) -> Option<Vec<u8>> {
+        while self.data.len() < self.at + len {
+            /* Each block starts with its size, the highest bit set when it's stored uncompressed. */
+            let header = le16(&self.image.read_at(self.next, 2)?, 0)?;
+            let raw = self
+                .image
+                .read_at(self.next + 2, (header & 0x7fff) as usize)?;
+            let block = match header & 0x8000 {
+                0 => self.image.decompress(&raw, METADATA_SIZE)?,
+                _ => raw,
+            };
+            if block.is_empty() {
+                return None;
+            }
+            self.next += 2 + (header & 0x7fff) as u64;
+            self.data.extend(block);
+        }
+        let bytes = self.data[self.at..self.at + len].to_vec();
+        self.at += len;
+        Some(bytes)
+    }
+}
+
+fn le16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn le32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le64(data: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(at..at + 8)?.try_into().ok()?))
+}
+
+/*
+ * Builds an image holding the given files and symlinks in its root directory, for the tests. Everything is
+ * compressed with the given compressor, or stored uncompressed without one.
+ */
+#[cfg(test)]
+pub(crate) fn test_image(
+    files: &[(&str, &[u8])],
+    links: &[(&str, &str)],
+    compressor: Option<u16>,
+) -> Vec<u8> {
+    use std::io::Write;
+
+    let pack = |data: &[u8]| -> (Vec<u8>, bool) {
+        match compressor {
+            Some(GZIP) => {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).unwrap();
+                (encoder.finish().unwrap(), true)
+            }
+            Some(ZSTD) => {
+                let level = ruzstd::encoding::CompressionLevel::Fastest;
+                (ruzstd::encoding::compress_to_vec(data, level), true)
+            }
+            _ => (data.to_vec(), false),
+        }
+    };
+    let metadata_block = |data: &[u8]| {
+        let (packed, compressed) = pack(data);
+        let header = packed.len() as u16 | if compressed { 0 } else { 0x8000 };
+        [&header.to_le_bytes()[..], &packed].concat()
+    };
+    let inode_header = |kind: u16, number: u32| {
+        [&kind.to_le_bytes()[..], &[0; 10], &number.to_le_bytes()].concat()
+    };
+
+    let mut image = vec![0; 96];
+    let (mut inodes, mut listing) = (Vec::new(), Vec::new());
+    let count = files.len() + links.len();
+    listing.extend((count as u32 - 1).to_le_bytes());
+    listing.extend([0; 4]);
+    listing.extend(1u32.to_le_bytes());
+
+    for (i, (name, contents)) in files.iter().enumerate() {
+        let mut sizes = Vec::new();
+        let start = image.len() as u32;
+        for chunk in contents.chunks(4096) {
+            let (packed, compressed) = pack(chunk);
+            sizes.push(packed.len() as u32 | if compressed { 0 } else { UNCOMPRESSED });
+            image.extend(packed);
+        }
+        listing.extend(
+            [
+                &(inodes.len() as u16).to_le_bytes()[..],
+                &[0; 2],
+                &2u16.to_le_bytes(),
+            ]
+            .concat(),
+        );
+        listing.extend(((name.len() - 1) as u16).to_le_bytes());
+        listing.extend(name.as_bytes());
+        inodes.extend(inode_header(2, i as u32 + 2));
+        for value in [start, NO_FRAGMENT, 0, contents.len() as u32]
+            .into_iter()
+            .chain(sizes)
+        {
+            inodes.extend(value.to_le_bytes());
+        }
+    }
+    for (i, (name, target)) in links.iter().enumerate() {
+        listing.extend(
+            [
+                &(inodes.len() as u16).to_le_bytes()[..],
+                &[0; 2],
+                &3u16.to_le_bytes(),
+            ]
+            .concat(),
+        );
+        listing.extend(((name.len() - 1) as u16).to_le_bytes());
+        listing.extend(name.as_bytes());
+        inodes.extend(inode_header(3, (files.len() + i) as u32 + 2));
+        inodes.extend(1u32.to_le_bytes());
+        inodes.extend((target.len() as u32).to_le_bytes());
+        inodes.extend(target.as_bytes());
+    }
+    let root = inodes.len() as u64;
+    inodes.extend(inode_header(1, 1));
+    inodes.extend([0; 8]);
+    inodes.extend((listing.len() as u16 + 3).to_le_bytes());
+    inodes.extend([0; 6]);
+
+    let inode_table = image.len() as u64;
+    image.extend(metadata_block(&inodes));
+    let directory_table = image.len() as u64;
+    image.extend(metadata_block(&listing));
+
+    let superblock = [
+        MAGIC,
+        &(count as u32 + 1).to_le_bytes(),
+        &[0; 4],
+        &4096u32.to_le_bytes(),
+        &[0; 4],
+        &compressor.unwrap_or(GZIP).to_le_bytes(),
+        &12u16.to_le_bytes(),
+        &[0; 4],
+        &4u16.to_le_bytes(),
+        &0u16.to_le_bytes(),
+        &root.to_le_bytes(),
+        &(image.len() as u64).to_le_bytes(),
+        &[0xff; 16],
+        &inode_table.to_le_bytes(),
+        &directory_table.to_le_bytes(),
+        &[0xff; 16],
+    ]
+    .concat();
+    image[..96].copy_from_slice(&superblock);
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    fn open(name: &str, data: &[u8]) -> Option<Squashfs> {
+        let path = utils::test_dir(name).join("image");
+        std::fs::write(&path, data).unwrap();
+        Squashfs::open(&path, 0)
+    }
+
+    #[test]
+    fn files_are_read_through_links() {
+        let big = (0..10_000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        for compressor in [None, Some(GZIP), Some(ZSTD)] {
+            let data = test_image(
+                &[
+                    ("app.desktop", b"[Desktop Entry]\nName=App\n"),
+                    ("big", &big),
+                ],
+                &[("link.desktop", "./app.desktop"), ("loop", "loop")],
+                compressor,
+            );
+            let image = open(&format!("squashfs-read-{:?}", compressor), &data).unwrap();
+
+            assert_eq!(
+                image.list("").unwrap(),
+                ["app.desktop", "big", "link.desktop", "loop"]
+            );
+            assert_eq!(
+                image.read("app.desktop").unwrap(),
+                b"[Desktop Entry]\nName=App\n"
+            );
+            assert_eq!(
+                image.read("/link.desktop").unwrap(),
+                b"[Desktop Entry]\nName=App\n"
+            );
+            assert_eq!(image.read("big").unwrap(), big);
+            assert!(image.read("loop").is_none());
+            assert!(image.read("missing").is_none());
+            assert!(image.read("").is_none());
+        }
+    }
+
+    #[test]
+    fn corrupt_images_are_rejected() {
+        let data = test_image(&[("app.desktop", b"[Desktop Entry]\n")], &[], Some(ZSTD));
+        for len in 0..data.len() {
+            if let Some(image) = open("squashfs-truncated", &data[..len]) {
+                assert!(
+                    image.read("app.desktop").is_none(),
+                    "Truncated to {} bytes",
+                    len
+                );
+            }
+        }
+
+        let mut data = data;
+        data[12..16].copy_from_slice(&1000u32.to_le_bytes());
+        assert!(open("squashfs-block-size", &data).is_none());
+    }
+}
@@ -0,0 +1,349 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::error::Error;
+use crate::utils;
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+/*
+ * An implementation of the freedesktop.org Trash specification (https://specifications.freedesktop.org/trash-spec/).
+ * Files on the same filesystem as the home directory go to $XDG_DATA_HOME/Trash, anything else goes to the
+ * trash directory at the top of its own mount point, so trashing never has to copy data across filesystems.
+ */
+
+/* Where a path ended up after being moved to the trash. */
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct TrashedItem {
+    pub original: PathBuf,
+    /* The file or directory itself, under the "files" directory of the trash. */
+    pub trashed: PathBuf,
+    /* The .trashinfo file describing it, under the "info" directory of the trash. */
+    pub info: PathBuf,
+}
+
+/* A trash directory, along with the directory relative paths in its .trashinfo files start from. */
+struct TrashDir {
+    dir: PathBuf,
+    /* None for the home trash, whose .trashinfo files always contain absolute paths. */
+    topdir: Option<PathBuf>,
+}
+
+/* Moves a file or a directory to the trash. */
+pub fn trash(path: &Path) -> Result<TrashedItem, Error> {
+    let err = |e: io::Error| Error::CouldNotTrash(path.to_path_buf(), e);
+    let original = absolute(path).map_err(err)?;
+    let trash_dir = find_trash_dir(&original).map_err(err)?;
+    trash_into(original, &trash_dir).map_err(err)
+}
+
+/* Moves an absolute path to the given trash directory. */
+fn trash_into(original: PathBuf, trash_dir: &TrashDir) -> io::Result<TrashedItem> {
+    let files = trash_dir.dir.join("files");
+    let info = trash_dir.dir.join("info");
+    for dir in [&files, &info] {
+        DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    }
+
+    let info_path = trash_dir.topdir.as_deref().map_or_else(
+        || original.clone(),
+        |topdir| {
+            original
+                .strip_prefix(topdir)
+                .unwrap_or(&original)
+                .to_path_buf()
+        },
+    );
+    let (name, mut info_file) = reserve_name(&original, &files, &info)?;
+    let info_file_path = info.join(format!("{}.trashinfo", name));
+    let trashed = files.join(&name);
+
+    let result = write!(
+        info_file,
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(&info_path),
+        utils::format_timestamp(utils::now())
+    )
+    .and_then(|_| fs::rename(&original, &trashed));
+    if let Err(e) = result {
+        fs::remove_file(&info_file_path).ok();
+        return Err(e);
+    }
+
+    log::info!("Moved {} to {}", original.display(), trashed.display());
+    Ok(TrashedItem {
+        original,
+        trashed,
+        info: info_file_path,
+    })
+}
+
+/* Makes a path absolute without resolving symlinks, so a symlink is trashed rather than what it points to. */
+fn absolute(path: &Path) -> io::Result<PathBuf> {
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name"))?;
+    Ok(match parent {
+        Some(parent) => fs::canonicalize(parent)?.join(name),
+        None => std::env::current_dir()?.join(name),
+    })
+}
+
+fn find_trash_dir(path: &Path) -> io::Result<TrashDir> {
+    let data_home = utils::data_home().map_err(io::Error::other)?;
+    fs::create_dir_all(&data_home)?;
+    let device = fs::symlink_metadata(path)?.dev();
+
+    if fs::metadata(&data_home)?.dev() == device {
+        return Ok(TrashDir {
+            dir: data_home.join("Trash"),
+            topdir: None,
+        });
+    }
+
+    /* The top directory is the last ancestor that is still on the same filesystem as the path. */
+    let mut topdir = path.parent().unwrap_or(Path::new("/"));
+    while let Some(parent) = topdir.parent() {
+        if fs::metadata(parent)?.dev() != device {
+            break;
+        }
+        topdir = parent;
+    }
+
+    let uid = unsafe { libc::getuid() };
+    Ok(TrashDir {
+        dir: topdir_trash(topdir, uid)?,
+        topdir: Some(topdir.to_path_buf()),
+    })
+}
+
+/*
+ * The trash directory of a user at the top of a filesystem other than the home one: $topdir/.Trash/$uid
+ * when the administrator provides $topdir/.Trash, or else (or when that can't be used) $topdir/.Trash-$uid.
+ */
+fn topdir_trash(topdir: &Path, uid: u32) -> io::Result<PathBuf> {
+    let admin_trash = topdir.join(".Trash");
+    /* $topdir/.Trash is only usable if the administrator made it a sticky directory, and never if it is a symlink. */
+    if fs::symlink_metadata(&admin_trash)
+        .is_ok_and(|m| m.is_dir() && m.permissions().mode() & 0o1000 != 0)
+    {
+        let dir = admin_trash.join(uid.to_string());
+        if owned_dir(&dir, uid) || DirBuilder::new().mode(0o700).create(&dir).is_ok() {
+            return Ok(dir);
+        }
+        log::warn!(
+            "Couldn't use {}, using .Trash-{} instead",
+            dir.display(),
+            uid
+        );
+    }
+
+    let dir = topdir.join(format!(".Trash-{}", uid));
+    if fs::symlink_metadata(&dir).is_ok() && !owned_dir(&dir, uid) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is not a trash directory we can use", dir.display()),
+        ));
+    }
+    Ok(dir)
+}
+
+/* Whether a path is a directory (and not a symlink to one) owned by the user. */
+fn owned_dir(path: &Path, uid: u32) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|m| m.is_dir() && m.uid() == uid)
+}
+
+/*
+ * Picks a name that is free in the trash, by atomically creating its .trashinfo file. Names that
+ * are already taken get a number appended, like "data.2".
+ */
+fn reserve_name(original: &Path, files: &Path, info: &Path) -> io::Result<(String, fs::File)> {
+    let base = original
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    for n in 1.. {
+        let name = if n == 1 {
+            base.clone()
+        } else {
+            format!("{}.{}", base, n)
+        };
+        if fs::symlink_metadata(files.join(&name)).is_ok() {
+            continue;
+        }
+
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(info.join(format!("{}.trashinfo", name)))
+        {
+            Ok(file) => return Ok((name, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!()
+}
+
+/* Paths in .trashinfo files are escaped like in URLs, except for the slashes. */
+fn encode_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut encoded = String::new();
+    for &b in path.as_os_str().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+
+/* Paths in .trashinfo files, unescaped back. */
+pub fn decode_path(encoded: &str) -> PathBuf {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    PathBuf::from(OsStr::from_bytes(&decoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uid() -> u32 {
+        unsafe { libc::getuid() }
+    }
+
+    #[test]
+    fn sticky_admin_trash_is_used() {
+        let topdir = utils::test_dir("trash-sticky");
+        fs::create_dir(topdir.join(".Trash")).unwrap();
+        fs::set_permissions(topdir.join(".Trash"), fs::Permissions::from_mode(0o1777)).unwrap();
+        let dir = topdir_trash(&topdir, uid()).unwrap();
+        assert_eq!(dir, topdir.join(".Trash").join(uid().to_string()));
+        assert!(owned_dir(&dir, uid()));
+        assert_eq!(
+            fs::metadata(&dir).unwrap().permissions().mode() & 0o777,
+            0o700
+        );
+        /* Once created it is simply used again */
+        assert_eq!(topdir_trash(&topdir, uid()).unwrap(), dir);
+    }
+
+    #[test]
+    fn unusable_admin_trash_falls_back() {
+        let own = format!(".Trash-{}", uid());
+
+        /* Not sticky */
+        let topdir = utils::test_dir("trash-not-sticky");
+        fs::create_dir(topdir.join(".Trash")).unwrap();
+        fs::set_permissions(topdir.join(".Trash"), fs::Permissions::from_mode(0o777)).unwrap();
+        assert_eq!(topdir_trash(&topdir, uid()).unwrap(), topdir.join(&own));
+
+        /* Sticky, but $uid can't be created in it */
+        let topdir = utils::test_dir("trash-sticky-taken");
+        fs::create_dir(topdir.join(".Trash")).unwrap();
+        fs::set_permissions(topdir.join(".Trash"), fs::Permissions::from_mode(0o1777)).unwrap();
+        fs::write(topdir.join(".Trash").join(uid().to_string()), "").unwrap();
+        assert_eq!(topdir_trash(&topdir, uid()).unwrap(), topdir.join(&own));
+
+        /* A symlink is never followed */
+        let topdir = utils::test_dir("trash-symlink");
+        std::os::unix::fs::symlink("/tmp", topdir.join(".Trash")).unwrap();
+        assert_eq!(topdir_trash(&topdir, uid()).unwrap(), topdir.join(&own));
+        std::os::unix::fs::symlink("/tmp", topdir.join(&own)).unwrap();
+        assert!(topdir_trash(&topdir, uid()).is_err());
+    }
+
+    #[test]
+    fn trash_info_is_written() {
+        let dir = utils::test_dir("trash-info");
+        let original = dir.join("my data%.txt");
+        fs::write(&original, "data").unwrap();
+
+        /* The home trash records absolute paths */
+        let home = TrashDir {
+            dir: dir.join("Trash"),
+            topdir: None,
+        };
+        let item = trash_into(original.clone(), &home).unwrap();
+        assert!(!original.exists());
+        assert_eq!(item.trashed, dir.join("Trash/files/my data%.txt"));
+        assert_eq!(item.info, dir.join("Trash/info/my data%.txt.trashinfo"));
+        let info = fs::read_to_string(&item.info).unwrap();
+        let lines = info.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "[Trash Info]");
+        assert_eq!(
+            lines[1],
+            format!("Path={}", encode_path(&original)),
+            "{}",
+            info
+        );
+        assert!(lines[1].ends_with("/my%20data%25.txt"));
+        assert_eq!(
+            decode_path(lines[1].strip_prefix("Path=").unwrap()),
+            original
+        );
+        let date = lines[2].strip_prefix("DeletionDate=").unwrap();
+        assert_eq!(date.len(), 19, "{}", date);
+        for (i, c) in date.chars().enumerate() {
+            match i {
+                4 | 7 => assert_eq!(c, '-'),
+                10 => assert_eq!(c, 'T'),
+                13 | 16 => assert_eq!(c, ':'),
+                _ => assert!(c.is_ascii_digit(), "{}", date),
+            }
+        }
+
+        /* Trashing the same name again picks another one, and topdir trashes record relative paths */
+        fs::write(&original, "again").unwrap();
+        let topdir = TrashDir {
+            dir: dir.join("Trash"),
+            topdir: Some(dir.clone()),
+        };
+        let item = trash_into(original.clone(), &topdir).unwrap();
+        assert_eq!(item.trashed, dir.join("Trash/files/my data%.txt.2"));
+        let info = fs::read_to_string(&item.info).unwrap();
+        assert!(info.contains("\nPath=my%20data%25.txt\n"), "{}", info);
+    }
+}
@@ -0,0 +1,230 @@
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::desktop::DesktopEntry;
+use crate::exec;
+use crate::installroot;
+use crate::purge::{AppPurgeProcess, AppPurger, Confidence};
+use crate::utils::{self, format_size};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/* What part of an app some of the space it takes belongs to. */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageKind {
+    Executable,
+    InstallRoot,
+    Data,
+    Config,
+    Cache,
+    State,
+}
+
+impl fmt::Display for UsageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Executable => "executable",
+            Self::InstallRoot => "install directory",
+            Self::Data => "data",
+            Self::Config => "configuration",
+            Self::Cache => "cache",
+            Self::State => "state",
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UsagePart {
+    pub kind: UsageKind,
+    pub paths: Vec<PathBuf>,
+    /* Space taken on disk, in bytes. */
+    pub size: u64,
+}
+
+/* The space an app takes on disk, broken down by what it is used for. */
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct AppUsage {
+    pub parts: Vec<UsagePart>,
+}
+
+impl AppUsage {
+    /*
+     * Measures everything of an app that is found on disk: the executable (with the symlinks and wrapper
+     * scripts leading to it), its install root and the data, configuration, cache and state it left around
+     * that is more than a coincidence (see AppPurgeProcess::find_leftovers()).
+     */
+    pub fn of_entry(entry: &DesktopEntry) -> Self {
+        let mut executables = Vec::new();
+        match &entry.appimage {
+            Some(image) => executables.push(image.path.clone()),
+            None => {
+                if let Some(exec) = AppPurger::find_exec(entry) {
+                    executables.extend(exec::follow_launcher(&exec));
+                    executables.insert(0, exec);
+                }
+            }
+        }
+
+        let mut groups = vec![
+            (UsageKind::Executable, executables),
+            (
+                UsageKind::InstallRoot,
+                installroot::find_root(entry).into_iter().collect(),
+            ),
+        ];
+        /* Both where the XDG base directories are and where they are by default, as some apps ignore them */
+        let home = utils::home_dir().ok();
+        let bases = [
+            (UsageKind::Config, utils::config_home().ok(), ".config"),
+            (UsageKind::Cache, utils::cache_home().ok(), ".cache"),
+            (UsageKind::State, utils::state_home().ok(), ".local/state"),
+        ];
+        /* Coincidences aren't removed by default, so they don't count either */
+        for leftover in AppPurgeProcess::for_entry(entry)
+            .find_leftovers()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|l| l.confidence > Confidence::Low)
+        {
+            let path = leftover.path;
+            let kind = bases
+                .iter()
+                .find(|(_, base, default)| {
+                    base.as_ref().is_some_and(|b| path.starts_with(b))
+                        || home
+                            .as_ref()
+                            .is_some_and(|h| path.starts_with(h.join(default)))
+                })
+                .map_or(UsageKind::Data, |(kind, ..)| *kind);
+            match groups.iter_mut().find(|(k, _)| *k == kind) {
+                Some((_, paths)) => paths.push(path),
+                None => groups.push((kind, vec![path])),
+            }
+        }
+        Self::measure(groups)
+    }
+
+    /*
+     * Measures groups of paths, counting whatever two of them share only once: files hardlinked from
+     * several places, and paths inside another one (whose group gets them, not theirs).
+     */
+    pub fn measure(groups: Vec<(UsageKind, Vec<PathBuf>)>) -> Self {
+        let all = groups
+            .iter()
+            .flat_map(|(_, paths)| paths.clone())
+            .collect::<Vec<_>>();
+        let walker = Walker::default();
+        let mut claimed = Vec::new();
+        let mut parts = Vec::new();
+        for (kind, paths) in groups {
+            let mut part = UsagePart {
+                kind,
+                paths: Vec::new(),
+                size: 0,
+            };
+            for path in paths {
+                if claimed.contains(&path) || all.iter().any(|a| *a != path && path.starts_with(a))
+                {
+                    continue;
+                }
+                part.size += walker.size(&path, None);
+                part.paths.push(path.clone());
+                claimed.push(path);
+            }
+            if !part.paths.is_empty() {
+                parts.push(part);
+            }
+        }
+        Self { parts }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.parts.iter().map(|p| p.size).sum()
+    }
+
+    /* The total followed by what it is made of, eg. "12.5 MB (executable 1.2 MB, cache 11.3 MB)". */
+    pub fn summary(&self) -> String {
+        let parts = self
+            .parts
+            .iter()
+            .map(|p| format!("{} {}", p.kind, format_size(p.size)))
+            .collect::<Vec<_>>();
+        if parts.len() < 2 {
+            format_size(self.total())
+        } else {
+            format!("{} ({})", format_size(self.total()), parts.join(", "))
+        }
+    }
+}
+
+/*
+ * Returns the space a file, or everything inside a directory, takes on disk in bytes. See Walker for what is
+ * counted.
+ */
+pub fn disk_usage(path: &Path) -> u64 {
+    Walker::default().size(path, None)
+}
+
+/*
+ * Walks directories in parallel like `du -x` does: without crossing into other filesystems and without
+ * following symlinks, counting hardlinked files once.
+ */
+#[derive(Default)]
+struct Walker {
+    /* Device and inode of the files with more than one link seen so far. */
+    seen: Mutex<HashSet<(u64, u64)>>,
+}
+
+impl Walker {
+    /* Entries that can't be read are counted as empty. */
+    fn size(&self, path: &Path, device: Option<u64>) -> u64 {
+        let Ok(metadata) = fs::symlink_metadata(path) else {
+            return 0;
+        };
+        if device.is_some_and(|d| d != metadata.dev()) {
+            return 0;
+        }
+        if !metadata.is_dir() && metadata.nlink() > 1 {
+            let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+            if !seen.insert((metadata.dev(), metadata.ino())) {
+                return 0;
+            }
+        }
+
+        /* st_blocks is always in units of 512 bytes, whatever the block size of the filesystem */
+        let own = metadata.blocks() * 512;
+        if !metadata.is_dir() {
+            return own;
+        }
+        let Ok(entries) = fs::read_dir(path) else {
+            return own;
+        };
+        let children = entries.flatten().map(|e| e.path()).collect::<Vec<_>>();
+        own + children
+            .par_iter()
+            .map(|child| self.size(child, Some(metadata.dev())))
+            .sum::<u64>()
+    }
+}
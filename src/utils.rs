@@ -16,29 +16,114 @@
 ///  
 /// You should have received a copy of the GNU General Public License  
 /// along with this program.  If not, see <https://www.gnu.org/licenses/>.
-use std::{fs, io};
-
-/* Isolates the executable from the rest of the command using whitespace detection. */
-#[inline]
-pub fn isolate_exec(cmd: String) -> String {
-    cmd.split_whitespace().next().unwrap_or("").to_owned()
-}
-
-/*
- * Takes a full path to a command, eg. /usr/bin/ls and returns the name of the command,
- * without the directory.
- */
-#[inline]
-pub fn omit_dir_from_cmd(cmd: String) -> String {
-    isolate_exec(cmd.rsplit('/').next().unwrap_or(&cmd).to_owned())
-}
-
-/*
- * Returns the size of the file specified in KBs
- */
-#[inline]
-pub fn get_file_size(path: impl Into<String>) -> io::Result<String> {
-    let metadata = fs::metadata(path.into())?;
-    let size_in_bytes = metadata.len();
-    Ok((size_in_bytes as f64 / (1024.0)).round().to_string())
+use crate::error::Error;
+use std::path::PathBuf;
+
+/* Home directory of the current user. */
+pub fn home_dir() -> Result<PathBuf, Error> {
+    /* We can allow this function even though its deprecated because this app is not designed for Windows. */
+    #[allow(deprecated)]
+    std::env::home_dir().ok_or(Error::HomeNotFound)
+}
+
+/* $XDG_DATA_HOME, or ~/.local/share when it isn't set (or isn't absolute, as the spec requires). */
+pub fn data_home() -> Result<PathBuf, Error> {
+    match std::env::var_os("XDG_DATA_HOME").map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => Ok(dir),
+        _ => Ok(home_dir()?.join(".local/share")),
+    }
+}
+
+/* $XDG_CONFIG_HOME, or ~/.config when it isn't set. */
+pub fn config_home() -> Result<PathBuf, Error> {
+    match std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => Ok(dir),
+        _ => Ok(home_dir()?.join(".config")),
+    }
+}
+
+/* $XDG_CACHE_HOME, or ~/.cache when it isn't set. */
+pub fn cache_home() -> Result<PathBuf, Error> {
+    match std::env::var_os("XDG_CACHE_HOME").map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => Ok(dir),
+        _ => Ok(home_dir()?.join(".cache")),
+    }
+}
+
+/* $XDG_STATE_HOME, or ~/.local/state when it isn't set. */
+pub fn state_home() -> Result<PathBuf, Error> {
+    match std::env::var_os("XDG_STATE_HOME").map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => Ok(dir),
+        _ => Ok(home_dir()?.join(".local/state")),
+    }
+}
+
+/* $XDG_DATA_DIRS in order of preference, or /usr/local/share and /usr/share when it isn't set. */
+pub fn data_dirs() -> Vec<PathBuf> {
+    let dirs = std::env::var("XDG_DATA_DIRS").unwrap_or_default();
+    let dirs = dirs
+        .split(':')
+        .map(PathBuf::from)
+        .filter(|d| d.is_absolute())
+        .collect::<Vec<_>>();
+    if dirs.is_empty() {
+        vec![
+            PathBuf::from("/usr/local/share"),
+            PathBuf::from("/usr/share"),
+        ]
+    } else {
+        dirs
+    }
+}
+
+/* Formats a size in bytes for humans, eg. 1536 becomes "1.5 KB". */
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/* Seconds since the Unix epoch. */
+pub fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/* Formats a Unix timestamp in local time as YYYY-MM-DDThh:mm:ss. */
+pub fn format_timestamp(secs: i64) -> String {
+    unsafe {
+        let time = secs as libc::time_t;
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&time, &mut tm);
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            tm.tm_year + 1900,
+            tm.tm_mon + 1,
+            tm.tm_mday,
+            tm.tm_hour,
+            tm.tm_min,
+            tm.tm_sec
+        )
+    }
+}
+
+/* An empty directory for the fixtures of a test, named after it so tests running in parallel don't clash. */
+#[cfg(test)]
+pub(crate) fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("appuninstaller-{}-{}", std::process::id(), name));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).expect("The temporary directory must be writable");
+    dir
 }
@@ -0,0 +1,723 @@
+#![allow(deprecated)]
+
+// This file is part of Linux Program Uninstaller.
+///
+/// Linux Uninstaller - A fast, elegant program uninstaller for Linux
+///  
+/// Copyright (C) 2025 Aggelos Tselios  
+///  
+/// This program is free software: you can redistribute it and/or modify  
+/// it under the terms of the GNU General Public License as published by  
+/// the Free Software Foundation, either version 3 of the License, or  
+/// (at your option) any later version.  
+///  
+/// This program is distributed in the hope that it will be useful,  
+/// but WITHOUT ANY WARRANTY; without even the implied warranty of  
+/// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the  
+/// GNU General Public License for more details.  
+///  
+/// You should have received a copy of the GNU General Public License  
+/// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use crate::dialog::Dialog;
+use crate::preferences::Preferences;
+use appuninstaller::desktop::DesktopEntry;
+use appuninstaller::error::Error;
+use appuninstaller::exec::{self, ExecCommand};
+use appuninstaller::flatpak;
+use appuninstaller::history::{History, HistoryEntry};
+use appuninstaller::installroot;
+use appuninstaller::pkgdb::{PackageDb, PackageOwner};
+use appuninstaller::pkgmgr;
+use appuninstaller::plan::UninstallPlan;
+#[cfg(debug_assertions)]
+use appuninstaller::purge::AppPurgeProcess;
+use appuninstaller::purge::{AppPurger, RemovalMethod};
+use appuninstaller::snap;
+use appuninstaller::usage::{self, AppUsage};
+use appuninstaller::utils::format_size;
+use gtk::{gio, glib, prelude::*, Align, Dialog as GtkDialog, ResponseType};
+use gtk::{Button, CheckButton, FileDialog, Image, Label, ScrolledWindow, TextView};
+use std::cell::RefCell;
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/* What the thread running the package manager reports back to the UI. */
+enum Progress {
+    Line(String),
+    Done(Result<(), Error>),
+}
+
+/* The GTK side of a desktop entry: its button in the app list and the detailed view next to it. */
+pub trait EntryView {
+    fn create_button_from_entry(&self) -> Button;
+    fn appview(&self) -> gtk::Box;
+}
+
+impl EntryView for DesktopEntry {
+    fn create_button_from_entry(&self) -> Button {
+        let button = Button::new();
+        let container = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+        let icon = Image::from_icon_name(
+            &self
+                .icon_path
+                .clone()
+                .unwrap_or("question-symbolic".to_string()),
+        );
+        let label = Label::new(Some(&match (self.broken, &self.hidden) {
+            (true, _) => format!("{} (broken)", self.name),
+            (false, Some(_)) => format!("{} (hidden)", self.name),
+            (false, None) => self.name.clone(),
+        }));
+
+        label.set_halign(gtk::Align::Center);
+        container.append(&icon);
+        container.append(&label);
+        button.set_child(Some(&container));
+
+        button
+    }
+
+    fn appview(&self) -> gtk::Box {
+        let view = gtk::Box::new(gtk::Orientation::Vertical, 16);
+        let title = Label::new(None);
+        let exec_widget = Label::new(Some(&format!("Command: {}", self.exec)));
+        let desc = Label::new(Some(&format!("Description: {}", self.description.clone())));
+        let full = Label::new(Some(&format!("Desktop entry path: {}", self.full_path)));
+        let flatpak_app = flatpak::find_app(self);
+        let snap = snap::find_snap(self);
+        let exec_path = AppPurger::find_exec(self);
+        let hops = exec_path
+            .as_deref()
+            .map(exec::follow_launcher)
+            .unwrap_or_default();
+        let filesize = Label::new(None);
+        match (&flatpak_app, &snap) {
+            (Some(app), _) => {
+                filesize.set_label(&format!("Size on disk: {}", format_size(app.size)))
+            }
+            (None, Some(snap)) => {
+                filesize.set_label(&format!("Size on disk: {}", format_size(snap.size)))
+            }
+            (None, None) => {
+                /* Big install trees take a while to walk, so it is done in the background */
+                filesize.set_label("Size on disk: calculating...");
+                let (tx, rx) = mpsc::channel();
+                let entry = self.clone();
+                std::thread::spawn(move || tx.send(AppUsage::of_entry(&entry).summary()));
+                let label = filesize.clone();
+                glib::timeout_add_local(Duration::from_millis(100), move || match rx.try_recv() {
+                    Ok(summary) => {
+                        label.set_label(&format!("Size on disk: {}", summary));
+                        glib::ControlFlow::Break
+                    }
+                    Err(mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+                    Err(mpsc::TryRecvError::Disconnected) => glib::ControlFlow::Break,
+                });
+            }
+        }
+        let owner = PackageDb::system().entry_owner(self);
+        let package = Label::new(Some(&format!(
+            "Package: {}",
+            match (owner, &flatpak_app, &snap) {
+                (Some(owner), _, _) => owner.to_string(),
+                (None, Some(_), _) => "None, installed with Flatpak".to_string(),
+                (None, None, Some(_)) => "None, installed with snap".to_string(),
+                (None, None, None) => "None, installed manually".to_string(),
+            }
+        )));
+        let source = Label::new(None);
+        match (&flatpak_app, &snap) {
+            (Some(app), _) => source.set_label(&format!(
+                "Flatpak: {} from {} ({} installation)",
+                app.name(),
+                app.origin.as_deref().unwrap_or("an unknown remote"),
+                app.installation
+            )),
+            (None, Some(snap)) => source.set_label(&format!(
+                "Snap: {} {} (revision {})",
+                snap.name,
+                snap.version.as_deref().unwrap_or(""),
+                snap.revision
+            )),
+            (None, None) => match &self.appimage {
+                Some(image) => source.set_label(&format!(
+                    "AppImage: {} (version {})",
+                    image.path.display(),
+                    image.version.as_deref().unwrap_or("unknown")
+                )),
+                None => match ExecCommand::parse(&self.exec)
+                    .ok()
+                    .filter(|c| c.interpreter.is_some())
+                {
+                    Some(command) => source.set_label(&format!(
+                        "Interpreter: {}, running {}",
+                        command.interpreter.as_deref().unwrap_or_default(),
+                        command
+                            .payload
+                            .as_deref()
+                            .unwrap_or("code from the command line")
+                    )),
+                    None => source.set_visible(false),
+                },
+            },
+        }
+        let location = Label::builder()
+            .label(format!(
+                "Real location: {}",
+                hops.iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" → ")
+            ))
+            .visible(!hops.is_empty())
+            .build();
+        let root = installroot::find_root(self);
+        let install_root = Label::builder()
+            .label(root.as_ref().map_or_else(String::new, |root| {
+                format!("Installed in: {}", root.display())
+            }))
+            .visible(root.is_some())
+            .build();
+        let shadows = Label::builder()
+            .label(format!("Shadows: {}", self.shadowed.join(", ")))
+            .visible(!self.shadowed.is_empty())
+            .build();
+        let mut notes = Vec::new();
+        if self.broken {
+            notes.push("Broken: the program its TryExec key names is missing".to_string());
+        }
+        if let Some(reason) = &self.hidden {
+            notes.push(format!("Hidden from application menus, as {}", reason));
+        }
+        let state = Label::builder()
+            .label(notes.join("\n"))
+            .visible(!notes.is_empty())
+            .build();
+        let openbtn = Button::with_label("Open externally");
+        let dltapp = Button::builder()
+            .label("Delete Application (!)")
+            .css_classes(vec!["destructive-action"])
+            .build();
+        let overridebtn = Button::builder()
+            .label("Remove user override")
+            .tooltip_text("Removes only your copy of the desktop entry, the system one takes its place again.")
+            .visible(self.is_user_override())
+            .build();
+        let pkgbtn = Button::builder()
+            .label("Remove with package manager")
+            .css_classes(vec!["destructive-action"])
+            .visible(owner.is_some())
+            .build();
+        let output = TextView::builder()
+            .editable(false)
+            .cursor_visible(false)
+            .monospace(true)
+            .build();
+        let output_scroll = ScrolledWindow::builder()
+            .child(&output)
+            .min_content_height(200)
+            .visible(false)
+            .build();
+        #[cfg(debug_assertions)]
+        let opendata = Button::builder()
+            .label("Open Data Folder(s)")
+            .tooltip_text(r#"Only available on debug builds. Opens the directory that AppUninstaller thinks belongs to this application. Useful to see how accurate is the internal algorithm for detection.
+            Note that if many directories are found only the last one detected will be used."#)
+            .build();
+
+        /* Apparently we can't just use markup directly, we need to set it manually here. */
+        title.set_markup(&format!(
+            "<b><span size='xx-large'>App: {}</span></b>",
+            self.name
+        ));
+        title.set_halign(Align::Start);
+        exec_widget.set_halign(Align::Start);
+        desc.set_halign(Align::Start);
+
+        let app = self.clone();
+        let entry_c = PathBuf::from(self.full_path.clone());
+
+        openbtn.connect_clicked(move |_| {
+            open::that_detached(format!("file://{}", entry_c.to_string_lossy())).unwrap_or_else(
+                |e| {
+                    Dialog::new_without_parent(
+                        "Error!",
+                        &format!("Could not open file '{}': {}.", entry_c.display(), e),
+                    );
+                    log::error!("Failed to open {}: {}", entry_c.display(), e)
+                },
+            )
+        });
+        openbtn.set_tooltip_text(Some("Opens the desktop entry using your system's preconfigured application. Useful if you want to modify something in it."));
+        openbtn.set_sensitive(OpenOptions::new().read(true).open(&self.full_path).is_ok());
+
+        let allow_packaged = Preferences::load().allow_packaged;
+        if owner.is_some() && !allow_packaged {
+            dltapp.set_sensitive(false);
+            dltapp.set_tooltip_text(Some("This app belongs to a package of your package manager, use it to remove the app instead. This can be overridden in the preferences."));
+        }
+        if let Some(app) = flatpak_app {
+            let output_scroll = output_scroll.clone();
+            let output = output.clone();
+            dltapp.set_label("Uninstall Flatpak");
+            dltapp.connect_clicked(move |b| {
+                uninstall_dialog(
+                    &format!("Uninstall {}", app.id),
+                    &format!(
+                        "{} is a Flatpak app from {}. It will be uninstalled with:",
+                        app.id,
+                        app.origin.as_deref().unwrap_or("an unknown remote")
+                    ),
+                    app.uninstall_command(),
+                    app.data_dir().ok(),
+                    b,
+                    &output_scroll,
+                    &output,
+                );
+            });
+        } else if let Some(snap) = snap {
+            let output_scroll = output_scroll.clone();
+            let output = output.clone();
+            dltapp.set_label("Remove Snap");
+            dltapp.connect_clicked(move |b| {
+                uninstall_dialog(
+                    &format!("Remove {}", snap.name),
+                    &format!(
+                        "Revision {} of the snap {} ({}) will be removed with:",
+                        snap.revision,
+                        snap.name,
+                        format_size(snap.size)
+                    ),
+                    snap.remove_command(),
+                    snap.data_dir().ok(),
+                    b,
+                    &output_scroll,
+                    &output,
+                );
+            });
+        } else {
+            /* Sizing everything and scanning for leftovers takes a while, so the plan is built off the main thread. */
+            dltapp.connect_clicked(move |b| {
+                b.set_sensitive(false);
+                let app = app.clone();
+                let button = b.clone();
+                in_background(
+                    move || UninstallPlan::new(&app, allow_packaged),
+                    move |plan| {
+                        button.set_sensitive(true);
+                        match plan {
+                            Ok(plan) => plan_dialog(plan),
+                            Err(e) => Dialog::new_without_parent("Error", &e.to_string()).show(),
+                        }
+                    },
+                );
+            });
+        }
+
+        {
+            let app = self.clone();
+            overridebtn.connect_clicked(move |_| match UninstallPlan::user_override(&app) {
+                Ok(plan) => plan_dialog(plan),
+                Err(e) => Dialog::new_without_parent("Error", &e.to_string()).show(),
+            });
+        }
+
+        if let Some(owner) = owner {
+            let output_scroll = output_scroll.clone();
+            pkgbtn.set_tooltip_text(Some(&format!(
+                "Removes the package {} with your package manager, which will ask for your password.",
+                owner.package
+            )));
+            pkgbtn.connect_clicked(move |b| {
+                package_dialog(owner, b, &output_scroll, &output);
+            });
+        }
+
+        #[cfg(debug_assertions)]
+        let purger = AppPurgeProcess::for_entry(self);
+        #[cfg(debug_assertions)]
+        opendata.connect_clicked(move |b| {
+            let dir_g = purger.find_app_files_global();
+            let dir_l = purger.find_app_files_home().unwrap_or_default();
+            if !dir_g.is_empty() {
+                let dir = &dir_g.last().unwrap().path;
+                open::that_detached(dir).unwrap_or_else(|e| {
+                    log::error!(
+                        "Couldn't open directory {}: {}",
+                        dir.display(),
+                        e.to_string()
+                    );
+                });
+            }
+
+            if !dir_l.is_empty() {
+                let dir = &dir_l.last().unwrap().path;
+                open::that_detached(dir).unwrap_or_else(|e| {
+                    log::error!(
+                        "Couldn't open directory {}: {}",
+                        dir.display(),
+                        e.to_string()
+                    );
+                });
+            }
+
+            if dir_g.is_empty() && dir_l.is_empty() {
+                b.set_label("No directories were found in the system.");
+            }
+        });
+
+        view.set_margin_start(16);
+        view.set_margin_end(16);
+        view.set_margin_top(16);
+        view.set_margin_bottom(16);
+        view.set_halign(Align::Start);
+        view.append(&title);
+        view.append(&exec_widget);
+        view.append(&desc);
+        view.append(&full);
+        view.append(&filesize);
+        view.append(&package);
+        view.append(&source);
+        view.append(&location);
+        view.append(&install_root);
+        view.append(&shadows);
+        view.append(&state);
+
+        /* The brackets aren't needed here, it's just for readability. */
+        {
+            let c = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+            c.append(&openbtn);
+            #[cfg(debug_assertions)]
+            c.append(&opendata);
+            c.append(&dltapp);
+            c.append(&overridebtn);
+            c.append(&pkgbtn);
+            view.append(&c);
+        }
+        view.append(&output_scroll);
+
+        view
+    }
+}
+
+/*
+ * Shows everything the plan would remove and lets the user uncheck what should be kept. Nothing is removed
+ * until "Remove selected" is clicked, and then exactly the checked items are.
+ */
+fn plan_dialog(plan: UninstallPlan) {
+    let dialog = GtkDialog::builder()
+        .title(format!("Uninstall {}", plan.app))
+        .icon_name("question-symbolic")
+        .modal(true)
+        .default_width(720)
+        .default_height(400)
+        .build();
+
+    let content = dialog.content_area();
+    content.set_spacing(8);
+    content.set_margin_start(8);
+    content.set_margin_end(8);
+    content.set_margin_top(8);
+    content.set_margin_bottom(8);
+    let method = Preferences::load().removal_method();
+    content.append(&Label::new(Some(match method {
+        RemovalMethod::Trash => "The following files and directories will be moved to the trash. Uncheck anything you want to keep.",
+        RemovalMethod::Permanent => "The following files and directories will be deleted permanently. Uncheck anything you want to keep.",
+    })));
+
+    let list = gtk::Box::new(gtk::Orientation::Vertical, 4);
+    let total = Label::new(Some(&format!("Total: {}", format_size(plan.total_size()))));
+    let plan = Rc::new(RefCell::new(plan));
+
+    for (i, item) in plan.borrow().items.iter().enumerate() {
+        let check = CheckButton::with_label(&format!(
+            "{} ({}, {} risk)\n{}",
+            item.path.display(),
+            format_size(item.size),
+            item.risk,
+            item.reason
+        ));
+        check.set_active(item.selected);
+        check.add_css_class(&format!("risk-{}", item.risk));
+
+        let plan = Rc::clone(&plan);
+        let total = total.clone();
+        check.connect_toggled(move |c| {
+            plan.borrow_mut().items[i].selected = c.is_active();
+            total.set_label(&format!(
+                "Total: {}",
+                format_size(plan.borrow().total_size())
+            ));
+        });
+        list.append(&check);
+    }
+
+    let scroll = ScrolledWindow::builder()
+        .child(&list)
+        .vexpand(true)
+        .hexpand(true)
+        .build();
+    content.append(&scroll);
+    total.set_halign(Align::Start);
+    content.append(&total);
+
+    dialog.add_button("Save plan...", ResponseType::Apply);
+    dialog.add_button("Cancel", ResponseType::Cancel);
+    dialog
+        .add_button("Remove selected", ResponseType::Accept)
+        .add_css_class("destructive-action");
+    dialog.set_default_response(ResponseType::Cancel);
+
+    dialog.connect_response(move |d, response| match response {
+        ResponseType::Accept => {
+            d.close();
+            let plan = plan.borrow();
+            let execution = plan.execute(method);
+            let recorded = History::open_default()
+                .and_then(|h| h.append(&HistoryEntry::new(&plan, &execution)));
+            let mut errors = execution.errors;
+            errors.extend(recorded.err());
+            for e in &errors {
+                Dialog::new_without_parent("Error", &e.to_string()).show();
+            }
+            if errors.is_empty() {
+                let msg = match method {
+                    RemovalMethod::Trash => format!("{} was moved to the trash.", plan.app),
+                    RemovalMethod::Permanent => format!("{} was removed.", plan.app),
+                };
+                Dialog::new_without_parent("Done", &msg).show();
+            }
+        }
+        ResponseType::Apply => save_plan(d, plan.borrow().clone()),
+        _ => d.close(),
+    });
+    dialog.present();
+}
+
+/* Asks where to save the plan, so it can be reviewed again or applied later with the command line interface. */
+fn save_plan(parent: &GtkDialog, plan: UninstallPlan) {
+    FileDialog::builder()
+        .title("Save uninstall plan")
+        .initial_name(format!("{}.json", plan.app))
+        .modal(true)
+        .build()
+        .save(Some(parent), None::<&gio::Cancellable>, move |file| {
+            let Some(path) = file.ok().and_then(|f| f.path()) else {
+                return;
+            };
+            if let Err(e) = plan.save(&path) {
+                Dialog::new_without_parent("Error", &e.to_string()).show();
+            }
+        });
+}
+
+/*
+ * Asks the package manager to remove the package, once the user has seen the packages that go along with
+ * it and the exact command it runs.
+ */
+fn package_dialog(
+    owner: &PackageOwner,
+    button: &Button,
+    output_scroll: &ScrolledWindow,
+    output: &TextView,
+) {
+    let backend = match pkgmgr::backend_for(owner, pkgmgr::backends()) {
+        Ok(backend) => backend,
+        Err(e) => return Dialog::new_without_parent("Error", &e.to_string()).show(),
+    };
+
+    /* The dry run can take a while when the package manager refreshes its metadata, so it runs off the main thread. */
+    button.set_sensitive(false);
+    let package = owner.package.clone();
+    let button = button.clone();
+    let output_scroll = output_scroll.clone();
+    let output = output.clone();
+    in_background(
+        move || {
+            let packages = pkgmgr::dry_run(backend.as_ref(), &package);
+            (backend, package, packages)
+        },
+        move |(backend, package, packages)| {
+            button.set_sensitive(true);
+            let packages = match packages {
+                Ok(packages) => packages,
+                Err(e) => return Dialog::new_without_parent("Error", &e.to_string()).show(),
+            };
+            let argv = pkgmgr::removal_command(backend.as_ref(), &package);
+            let dialog = command_dialog(
+                &format!("Remove {}", package),
+                &format!(
+                    "{} is managed by {}, removing it removes these packages:\n\n{}\n\nThe following command will be run:",
+                    package,
+                    backend.name(),
+                    packages.join("\n")
+                ),
+                &argv,
+            );
+            dialog.connect_response(move |d, response| {
+                d.close();
+                if response == ResponseType::Accept {
+                    run_in_view(argv.clone(), || Ok(()), &button, &output_scroll, &output);
+                }
+            });
+            dialog.present();
+        },
+    );
+}
+
+/*
+ * Uninstalls a Flatpak app or a snap with the given command, optionally wiping its data directory (if it
+ * has one) afterwards.
+ */
+fn uninstall_dialog(
+    title: &str,
+    intro: &str,
+    argv: Vec<String>,
+    data_dir: Option<PathBuf>,
+    button: &Button,
+    output_scroll: &ScrolledWindow,
+    output: &TextView,
+) {
+    let dialog = command_dialog(title, intro, &argv);
+    let data_dir = data_dir.filter(|d| d.exists());
+    let wipe = CheckButton::new();
+    if let Some(dir) = &data_dir {
+        wipe.set_label(Some(&format!(
+            "Also remove its data in {} ({})",
+            dir.display(),
+            format_size(usage::disk_usage(dir))
+        )));
+        wipe.set_active(true);
+        dialog.content_area().append(&wipe);
+    }
+
+    let button = button.clone();
+    let output_scroll = output_scroll.clone();
+    let output = output.clone();
+    dialog.connect_response(move |d, response| {
+        d.close();
+        if response != ResponseType::Accept {
+            return;
+        }
+
+        let method = Preferences::load().removal_method();
+        let data_dir = data_dir.clone().filter(|_| wipe.is_active());
+        run_in_view(
+            argv.clone(),
+            move || match data_dir {
+                Some(dir) if dir.exists() => AppPurger::remove_data(&dir, method).map(|_| ()),
+                _ => Ok(()),
+            },
+            &button,
+            &output_scroll,
+            &output,
+        );
+    });
+    dialog.present();
+}
+
+/* A dialog showing the exact command that is about to run, answered with ResponseType::Accept to run it. */
+fn command_dialog(title: &str, intro: &str, argv: &[String]) -> GtkDialog {
+    let dialog = GtkDialog::builder()
+        .title(title)
+        .icon_name("question-symbolic")
+        .modal(true)
+        .build();
+    let content = dialog.content_area();
+    content.set_spacing(8);
+    content.set_margin_start(8);
+    content.set_margin_end(8);
+    content.set_margin_top(8);
+    content.set_margin_bottom(8);
+    content.append(&Label::new(Some(intro)));
+    let command = Label::new(None);
+    command.set_markup(&format!(
+        "<tt>{}</tt>",
+        glib::markup_escape_text(&pkgmgr::display_command(argv))
+    ));
+    command.set_selectable(true);
+    content.append(&command);
+
+    dialog.add_button("Cancel", ResponseType::Cancel);
+    dialog
+        .add_button("Run", ResponseType::Accept)
+        .add_css_class("destructive-action");
+    dialog.set_default_response(ResponseType::Cancel);
+    dialog
+}
+
+/*
+ * Runs a command in the background, printing its output in the app view as it arrives. If it succeeds,
+ * `then` runs on the same background thread.
+ */
+/*
+ * Runs `work` on a worker thread and hands its result to `done` back on the main thread, for anything too slow
+ * to do while the window stops responding.
+ */
+fn in_background<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+    done: impl FnOnce(T) + 'static,
+) {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || tx.send(work()).unwrap_or(()));
+
+    let mut done = Some(done);
+    glib::timeout_add_local(Duration::from_millis(100), move || match rx.try_recv() {
+        Ok(result) => {
+            if let Some(done) = done.take() {
+                done(result);
+            }
+            glib::ControlFlow::Break
+        }
+        Err(mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+        Err(mpsc::TryRecvError::Disconnected) => glib::ControlFlow::Break,
+    });
+}
+
+fn run_in_view(
+    argv: Vec<String>,
+    then: impl FnOnce() -> Result<(), Error> + Send + 'static,
+    button: &Button,
+    output_scroll: &ScrolledWindow,
+    output: &TextView,
+) {
+    button.set_sensitive(false);
+    output_scroll.set_visible(true);
+    output.buffer().set_text("");
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let result = pkgmgr::run(&argv, |line| {
+            tx.send(Progress::Line(line.to_owned())).unwrap_or(())
+        })
+        .and_then(|_| then());
+        tx.send(Progress::Done(result)).unwrap_or(());
+    });
+
+    /* GTK widgets can only be touched from the main thread, so the output is picked up from here. */
+    let button = button.clone();
+    let output = output.clone();
+    glib::timeout_add_local(Duration::from_millis(100), move || {
+        let buffer = output.buffer();
+        for progress in rx.try_iter() {
+            match progress {
+                Progress::Line(line) => {
+                    buffer.insert(&mut buffer.end_iter(), &format!("{}\n", line))
+                }
+                Progress::Done(result) => {
+                    if let Err(e) = result {
+                        buffer.insert(&mut buffer.end_iter(), &format!("{}\n", e));
+                        button.set_sensitive(true);
+                        Dialog::new_without_parent("Error", &e.to_string()).show();
+                    }
+                    return glib::ControlFlow::Break;
+                }
+            }
+            output.scroll_to_iter(&mut buffer.end_iter(), 0.0, false, 0.0, 0.0);
+        }
+        glib::ControlFlow::Continue
+    });
+}